@@ -0,0 +1,274 @@
+use crate::actions::game_control::GameControl;
+use crate::persistence;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const BINDINGS_FILE: &str = "bindings.ron";
+
+/// How many keys can be bound to the same control
+pub const KEYS_PER_CONTROL: usize = 2;
+
+/// Keys that drive the rebinding itself or move the menu focus and can't be bound to a control
+pub const RESERVED_KEYS: [KeyCode; 3] = [KeyCode::Escape, KeyCode::Delete, KeyCode::Tab];
+
+/// Opens the menu on top of the bound keys, so the menu stays reachable whatever gets unbound
+pub const FIXED_MENU_KEY: KeyCode = KeyCode::Escape;
+
+/// Maps every [`GameControl`] to the keys that trigger it
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InputBindings {
+    keys: BTreeMap<GameControl, Vec<KeyCode>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BindingError {
+    /// The key already triggers another control
+    AlreadyBound(KeyCode, GameControl),
+    Reserved(KeyCode),
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingError::AlreadyBound(key, control) => {
+                write!(f, "{key:?} is already bound to {}", control.label())
+            }
+            BindingError::Reserved(key) => write!(f, "{key:?} can't be rebound"),
+        }
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keys = BTreeMap::from([
+            (GameControl::Up, vec![KeyCode::W, KeyCode::Up]),
+            (GameControl::Down, vec![KeyCode::S, KeyCode::Down]),
+            (GameControl::Left, vec![KeyCode::A, KeyCode::Left]),
+            (GameControl::Right, vec![KeyCode::D, KeyCode::Right]),
+            (GameControl::Confirm, vec![KeyCode::Return, KeyCode::Space]),
+            (GameControl::Cancel, vec![KeyCode::Back, KeyCode::Q]),
+            (GameControl::Menu, vec![KeyCode::P]),
+            (GameControl::Journal, vec![KeyCode::J]),
+            (GameControl::Dex, vec![KeyCode::G]),
+            (GameControl::Move1, vec![KeyCode::Key1]),
+            (GameControl::Move2, vec![KeyCode::Key2]),
+            (GameControl::Move3, vec![KeyCode::Key3]),
+            (GameControl::Move4, vec![KeyCode::Key4]),
+        ]);
+        InputBindings { keys }
+    }
+}
+
+impl InputBindings {
+    pub fn load() -> Self {
+        let mut bindings = persistence::load_or_default::<InputBindings>(BINDINGS_FILE);
+        bindings.add_missing_controls();
+        if let Some(conflict) = bindings.find_conflict() {
+            warn!("Saved key bindings are invalid ({conflict}), falling back to defaults");
+            return InputBindings::default();
+        }
+        bindings
+    }
+
+    pub fn keys(&self, control: GameControl) -> &[KeyCode] {
+        self.keys.get(&control).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The bound keys plus any key that always triggers `control`
+    pub fn triggers(&self, control: GameControl) -> impl Iterator<Item = KeyCode> + '_ {
+        let fixed = (control == GameControl::Menu).then_some(FIXED_MENU_KEY);
+        self.keys(control).iter().copied().chain(fixed)
+    }
+
+    pub fn control_for(&self, key: KeyCode) -> Option<GameControl> {
+        self.keys
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(control, _)| *control)
+    }
+
+    /// Binds `key` to `control`, replacing whatever was in `slot`
+    pub fn bind(
+        &mut self,
+        control: GameControl,
+        slot: usize,
+        key: KeyCode,
+    ) -> Result<(), BindingError> {
+        if RESERVED_KEYS.contains(&key) {
+            return Err(BindingError::Reserved(key));
+        }
+        if self.keys(control).get(slot) == Some(&key) {
+            return Ok(());
+        }
+        if let Some(bound_to) = self.control_for(key) {
+            return Err(BindingError::AlreadyBound(key, bound_to));
+        }
+        let keys = self.keys.entry(control).or_default();
+        if slot < keys.len() {
+            keys[slot] = key;
+        } else {
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    /// Removes the key in `slot` from `control`
+    pub fn unbind(&mut self, control: GameControl, slot: usize) {
+        let keys = self.keys.entry(control).or_default();
+        if slot < keys.len() {
+            keys.remove(slot);
+        }
+    }
+
+    /// Controls added after the bindings were saved get their default keys, unless those are taken
+    fn add_missing_controls(&mut self) {
+        for (control, keys) in InputBindings::default().keys {
+            if self.keys.contains_key(&control) {
+                continue;
+            }
+            let free = keys
+                .into_iter()
+                .filter(|key| self.control_for(*key).is_none())
+                .collect();
+            self.keys.insert(control, free);
+        }
+    }
+
+    /// Bindings read from disk might have been edited by hand, so check them like [`Self::bind`] would
+    fn find_conflict(&self) -> Option<BindingError> {
+        let mut seen: Vec<(KeyCode, GameControl)> = Vec::new();
+        for (control, keys) in &self.keys {
+            for key in keys {
+                if RESERVED_KEYS.contains(key) {
+                    return Some(BindingError::Reserved(*key));
+                }
+                if let Some((_, bound_to)) = seen.iter().find(|(seen_key, _)| seen_key == key) {
+                    return Some(BindingError::AlreadyBound(*key, *bound_to));
+                }
+                seen.push((*key, *control));
+            }
+        }
+        None
+    }
+}
+
+pub fn store_bindings(bindings: Res<InputBindings>) {
+    if bindings.is_added() {
+        return;
+    }
+    if let Err(error) = persistence::store(BINDINGS_FILE, &*bindings) {
+        warn!("Failed to save key bindings: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(keys: &[(GameControl, Vec<KeyCode>)]) -> InputBindings {
+        InputBindings {
+            keys: keys.iter().cloned().collect(),
+        }
+    }
+
+    #[test]
+    fn keys_bound_elsewhere_are_refused() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(
+            bindings.bind(GameControl::Up, 0, KeyCode::S),
+            Err(BindingError::AlreadyBound(KeyCode::S, GameControl::Down))
+        );
+        assert_eq!(bindings.keys(GameControl::Up), &[KeyCode::W, KeyCode::Up]);
+    }
+
+    #[test]
+    fn reserved_keys_are_refused() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(
+            bindings.bind(GameControl::Journal, 0, KeyCode::Tab),
+            Err(BindingError::Reserved(KeyCode::Tab))
+        );
+        assert_eq!(bindings.control_for(KeyCode::Tab), None);
+    }
+
+    #[test]
+    fn rebinding_the_same_slot_keeps_the_key() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(bindings.bind(GameControl::Up, 1, KeyCode::Up), Ok(()));
+        assert_eq!(bindings.keys(GameControl::Up), &[KeyCode::W, KeyCode::Up]);
+    }
+
+    #[test]
+    fn free_keys_replace_the_slot_or_are_added() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(bindings.bind(GameControl::Journal, 0, KeyCode::K), Ok(()));
+        assert_eq!(bindings.bind(GameControl::Journal, 1, KeyCode::L), Ok(()));
+        assert_eq!(
+            bindings.keys(GameControl::Journal),
+            &[KeyCode::K, KeyCode::L]
+        );
+        assert_eq!(bindings.control_for(KeyCode::J), None);
+    }
+
+    #[test]
+    fn escape_opens_the_menu_without_bound_keys() {
+        let mut bindings = InputBindings::default();
+        assert!(!bindings.keys(GameControl::Menu).contains(&FIXED_MENU_KEY));
+        bindings.unbind(GameControl::Menu, 0);
+        assert!(bindings.keys(GameControl::Menu).is_empty());
+        assert_eq!(
+            bindings.triggers(GameControl::Menu).collect::<Vec<_>>(),
+            [FIXED_MENU_KEY]
+        );
+        assert_eq!(bindings.find_conflict(), None);
+    }
+
+    #[test]
+    fn only_the_menu_has_a_fixed_key() {
+        let mut bindings = InputBindings::default();
+        bindings.unbind(GameControl::Journal, 0);
+        assert_eq!(bindings.triggers(GameControl::Journal).next(), None);
+    }
+
+    #[test]
+    fn default_bindings_are_valid() {
+        assert_eq!(InputBindings::default().find_conflict(), None);
+    }
+
+    #[test]
+    fn hand_edited_duplicates_are_found() {
+        let bindings = bindings(&[
+            (GameControl::Up, vec![KeyCode::W]),
+            (GameControl::Down, vec![KeyCode::W]),
+            (GameControl::Menu, vec![KeyCode::P]),
+        ]);
+        assert_eq!(
+            bindings.find_conflict(),
+            Some(BindingError::AlreadyBound(KeyCode::W, GameControl::Up))
+        );
+    }
+
+    #[test]
+    fn hand_edited_reserved_keys_are_found() {
+        let bindings = bindings(&[(GameControl::Menu, vec![KeyCode::Escape, KeyCode::P])]);
+        assert_eq!(
+            bindings.find_conflict(),
+            Some(BindingError::Reserved(KeyCode::Escape))
+        );
+    }
+
+    #[test]
+    fn missing_controls_only_get_free_default_keys() {
+        let mut bindings = bindings(&[
+            (GameControl::Up, vec![KeyCode::J]),
+            (GameControl::Menu, vec![KeyCode::P]),
+        ]);
+        bindings.add_missing_controls();
+        assert!(bindings.keys(GameControl::Journal).is_empty());
+        assert_eq!(bindings.keys(GameControl::Dex), &[KeyCode::G]);
+        assert_eq!(bindings.keys(GameControl::Up), &[KeyCode::J]);
+        assert_eq!(bindings.find_conflict(), None);
+    }
+}
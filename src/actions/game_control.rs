@@ -1,33 +1,86 @@
+use crate::actions::bindings::InputBindings;
 use bevy::prelude::{Input, KeyCode, Res};
+use serde::{Deserialize, Serialize};
 
+/// The logical actions the player can take, independent of the keys they are bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum GameControl {
     Up,
     Down,
     Left,
     Right,
+    Confirm,
+    Cancel,
+    Menu,
+    Journal,
+    Dex,
+    Move1,
+    Move2,
+    Move3,
+    Move4,
 }
 
 impl GameControl {
-    pub fn pressed(&self, keyboard_input: &Res<Input<KeyCode>>) -> bool {
+    pub const ALL: [GameControl; 13] = [
+        GameControl::Up,
+        GameControl::Down,
+        GameControl::Left,
+        GameControl::Right,
+        GameControl::Confirm,
+        GameControl::Cancel,
+        GameControl::Menu,
+        GameControl::Journal,
+        GameControl::Dex,
+        GameControl::Move1,
+        GameControl::Move2,
+        GameControl::Move3,
+        GameControl::Move4,
+    ];
+
+    pub const MOVES: [GameControl; 4] = [
+        GameControl::Move1,
+        GameControl::Move2,
+        GameControl::Move3,
+        GameControl::Move4,
+    ];
+
+    pub fn label(&self) -> &'static str {
         match self {
-            GameControl::Up => {
-                keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up)
-            }
-            GameControl::Down => {
-                keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down)
-            }
-            GameControl::Left => {
-                keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left)
-            }
-            GameControl::Right => {
-                keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right)
-            }
+            GameControl::Up => "Up",
+            GameControl::Down => "Down",
+            GameControl::Left => "Left",
+            GameControl::Right => "Right",
+            GameControl::Confirm => "Confirm",
+            GameControl::Cancel => "Cancel",
+            GameControl::Menu => "Menu",
+            GameControl::Journal => "Journal",
+            GameControl::Dex => "Garbagedex",
+            GameControl::Move1 => "Move 1",
+            GameControl::Move2 => "Move 2",
+            GameControl::Move3 => "Move 3",
+            GameControl::Move4 => "Move 4",
         }
     }
+
+    pub fn pressed(&self, bindings: &InputBindings, keyboard_input: &Res<Input<KeyCode>>) -> bool {
+        keyboard_input.any_pressed(bindings.triggers(*self))
+    }
+
+    pub fn just_pressed(
+        &self,
+        bindings: &InputBindings,
+        keyboard_input: &Res<Input<KeyCode>>,
+    ) -> bool {
+        keyboard_input.any_just_pressed(bindings.triggers(*self))
+    }
 }
 
-pub fn get_movement(control: GameControl, input: &Res<Input<KeyCode>>) -> f32 {
-    if control.pressed(input) {
+pub fn get_movement(
+    control: GameControl,
+    bindings: &InputBindings,
+    input: &Res<Input<KeyCode>>,
+) -> f32 {
+    if control.pressed(bindings, input) {
         1.0
     } else {
         0.0
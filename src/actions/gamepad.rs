@@ -0,0 +1,304 @@
+use crate::actions::game_control::GameControl;
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+
+/// Stick deflection below this is treated as resting
+///
+/// Applied to the length of the stick vector, so diagonals aren't cut off like with per axis dead zones.
+pub const STICK_DEADZONE: f32 = 0.25;
+
+/// The gamepad that currently drives the [`Actions`](crate::actions::Actions)
+///
+/// Picked up when the first gamepad connects and replaced by another connected one if it is unplugged.
+#[derive(Resource, Default)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+impl GameControl {
+    pub fn gamepad_buttons(&self) -> &'static [GamepadButtonType] {
+        match self {
+            GameControl::Up => &[GamepadButtonType::DPadUp],
+            GameControl::Down => &[GamepadButtonType::DPadDown],
+            GameControl::Left => &[GamepadButtonType::DPadLeft],
+            GameControl::Right => &[GamepadButtonType::DPadRight],
+            GameControl::Confirm => &[GamepadButtonType::South],
+            GameControl::Cancel => &[GamepadButtonType::East],
+            GameControl::Menu => &[GamepadButtonType::Start],
+            GameControl::Journal => &[GamepadButtonType::Select],
+            GameControl::Dex => &[GamepadButtonType::LeftThumb],
+            GameControl::Move1 => &[GamepadButtonType::West],
+            GameControl::Move2 => &[GamepadButtonType::North],
+            GameControl::Move3 => &[GamepadButtonType::LeftTrigger],
+            GameControl::Move4 => &[GamepadButtonType::RightTrigger],
+        }
+    }
+}
+
+/// Read access to the active gamepad, in terms of [`GameControl`]s
+#[derive(SystemParam)]
+pub struct GamepadInput<'w> {
+    active: Res<'w, ActiveGamepad>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl<'w> GamepadInput<'w> {
+    pub fn pressed(&self, control: GameControl) -> bool {
+        let Some(gamepad) = self.active.0 else {
+            return false;
+        };
+        self.buttons.any_pressed(
+            control
+                .gamepad_buttons()
+                .iter()
+                .map(|button_type| GamepadButton::new(gamepad, *button_type)),
+        )
+    }
+
+    pub fn just_pressed(&self, control: GameControl) -> bool {
+        let Some(gamepad) = self.active.0 else {
+            return false;
+        };
+        self.buttons.any_just_pressed(
+            control
+                .gamepad_buttons()
+                .iter()
+                .map(|button_type| GamepadButton::new(gamepad, *button_type)),
+        )
+    }
+
+    /// The left stick position with the dead zone cut out and the rest rescaled to `0..=1`
+    pub fn left_stick(&self) -> Option<Vec2> {
+        let gamepad = self.active.0?;
+        let stick = Vec2::new(
+            self.axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or(0.),
+            self.axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or(0.),
+        );
+        apply_deadzone(stick, STICK_DEADZONE)
+    }
+
+    /// Movement from the d-pad, or from the left stick if the d-pad is released
+    pub fn movement(&self) -> Option<Vec2> {
+        let axis = |positive: GameControl, negative: GameControl| {
+            self.pressed(positive) as i32 as f32 - self.pressed(negative) as i32 as f32
+        };
+        let dpad = Vec2::new(
+            axis(GameControl::Right, GameControl::Left),
+            axis(GameControl::Up, GameControl::Down),
+        );
+        if dpad != Vec2::ZERO {
+            return Some(dpad.normalize());
+        }
+        self.left_stick()
+    }
+}
+
+pub fn apply_deadzone(stick: Vec2, deadzone: f32) -> Option<Vec2> {
+    let length = stick.length();
+    if length <= deadzone {
+        return None;
+    }
+    let scaled_length = ((length - deadzone) / (1. - deadzone)).min(1.);
+    Some(stick / length * scaled_length)
+}
+
+/// Keeps [`ActiveGamepad`] pointing at a connected gamepad as they are plugged in and out
+pub fn select_active_gamepad(
+    mut active: ResMut<ActiveGamepad>,
+    gamepads: Res<Gamepads>,
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+) {
+    for event in connection_events.iter() {
+        match &event.connection {
+            GamepadConnection::Connected(info) if active.0.is_none() => {
+                info!("Using gamepad \"{}\"", info.name);
+                active.0 = Some(event.gamepad);
+            }
+            GamepadConnection::Disconnected if active.0 == Some(event.gamepad) => {
+                active.0 = gamepads.iter().find(|gamepad| *gamepad != event.gamepad);
+                match active.0.and_then(|gamepad| gamepads.name(gamepad)) {
+                    Some(name) => info!("Gamepad disconnected, switching to \"{name}\""),
+                    None => info!("Gamepad disconnected"),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::bindings::InputBindings;
+    use crate::actions::{set_menu_actions, set_movement_actions, Actions, NavigationDirection};
+    use bevy::input::gamepad::{
+        GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadEvent, GamepadInfo,
+    };
+    use bevy::input::{InputPlugin, InputSystem};
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugin(InputPlugin)
+            .init_resource::<Time>()
+            .init_resource::<Actions>()
+            .init_resource::<ActiveGamepad>()
+            .insert_resource(InputBindings::default())
+            .add_system(
+                select_active_gamepad
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem),
+            )
+            .add_system(
+                set_menu_actions
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(select_active_gamepad),
+            )
+            .add_system(set_movement_actions);
+        app
+    }
+
+    fn connect(app: &mut App, id: usize) -> Gamepad {
+        let gamepad = Gamepad::new(id);
+        app.world
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                gamepad,
+                GamepadConnection::Connected(GamepadInfo {
+                    name: format!("Test gamepad {id}"),
+                }),
+            )));
+        app.update();
+        gamepad
+    }
+
+    fn move_stick(app: &mut App, gamepad: Gamepad, x: f32, y: f32) {
+        app.world.send_event_batch([
+            GamepadEvent::Axis(GamepadAxisChangedEvent::new(
+                gamepad,
+                GamepadAxisType::LeftStickX,
+                x,
+            )),
+            GamepadEvent::Axis(GamepadAxisChangedEvent::new(
+                gamepad,
+                GamepadAxisType::LeftStickY,
+                y,
+            )),
+        ]);
+        app.update();
+    }
+
+    fn set_button(app: &mut App, gamepad: Gamepad, button: GamepadButtonType, value: f32) {
+        app.world
+            .send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(
+                gamepad, button, value,
+            )));
+        app.update();
+    }
+
+    #[test]
+    fn deadzone_rescales_the_remaining_range() {
+        assert_eq!(apply_deadzone(Vec2::new(0.2, 0.), 0.25), None);
+        assert_eq!(
+            apply_deadzone(Vec2::new(1., 0.), 0.25),
+            Some(Vec2::new(1., 0.))
+        );
+        let halfway = apply_deadzone(Vec2::new(0., -0.625), 0.25).unwrap();
+        assert!((halfway - Vec2::new(0., -0.5)).length() < 1e-5);
+    }
+
+    #[test]
+    fn first_connected_gamepad_becomes_active() {
+        let mut app = app();
+        let gamepad = connect(&mut app, 0);
+        connect(&mut app, 1);
+
+        assert_eq!(app.world.resource::<ActiveGamepad>().0, Some(gamepad));
+    }
+
+    #[test]
+    fn stick_moves_the_player_outside_of_the_deadzone() {
+        let mut app = app();
+        let gamepad = connect(&mut app, 0);
+
+        move_stick(&mut app, gamepad, 0.1, 0.1);
+        assert_eq!(app.world.resource::<Actions>().player_movement, None);
+
+        move_stick(&mut app, gamepad, 0., 1.);
+        assert_eq!(
+            app.world.resource::<Actions>().player_movement,
+            Some(Vec2::new(0., 1.))
+        );
+    }
+
+    #[test]
+    fn dpad_takes_precedence_over_the_stick() {
+        let mut app = app();
+        let gamepad = connect(&mut app, 0);
+
+        move_stick(&mut app, gamepad, 0., 1.);
+        set_button(&mut app, gamepad, GamepadButtonType::DPadLeft, 1.);
+
+        assert_eq!(
+            app.world.resource::<Actions>().player_movement,
+            Some(Vec2::new(-1., 0.))
+        );
+    }
+
+    #[test]
+    fn face_buttons_trigger_menu_actions_for_one_frame() {
+        let mut app = app();
+        let gamepad = connect(&mut app, 0);
+
+        set_button(&mut app, gamepad, GamepadButtonType::South, 1.);
+        assert!(app.world.resource::<Actions>().confirm);
+
+        app.update();
+        assert!(!app.world.resource::<Actions>().confirm);
+
+        set_button(&mut app, gamepad, GamepadButtonType::North, 1.);
+        assert_eq!(app.world.resource::<Actions>().selected_move, Some(1));
+    }
+
+    #[test]
+    fn stick_navigates_menus() {
+        let mut app = app();
+        let gamepad = connect(&mut app, 0);
+
+        move_stick(&mut app, gamepad, 0., -0.9);
+        assert_eq!(
+            app.world.resource::<Actions>().navigation,
+            Some(NavigationDirection::Down)
+        );
+
+        // holding the stick doesn't navigate again before the repeat delay
+        app.update();
+        assert_eq!(app.world.resource::<Actions>().navigation, None);
+    }
+
+    #[test]
+    fn unplugging_the_active_gamepad_switches_to_another_one() {
+        let mut app = app();
+        let first = connect(&mut app, 0);
+        let second = connect(&mut app, 1);
+
+        app.world
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                first,
+                GamepadConnection::Disconnected,
+            )));
+        app.update();
+        assert_eq!(app.world.resource::<ActiveGamepad>().0, Some(second));
+
+        app.world
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                second,
+                GamepadConnection::Disconnected,
+            )));
+        app.update();
+        assert_eq!(app.world.resource::<ActiveGamepad>().0, None);
+        assert_eq!(app.world.resource::<Actions>().player_movement, None);
+    }
+}
@@ -1,37 +1,176 @@
+use bevy::input::InputSystem;
 use bevy::prelude::*;
 
+use crate::actions::bindings::{store_bindings, InputBindings};
 use crate::actions::game_control::{get_movement, GameControl};
+use crate::actions::gamepad::{select_active_gamepad, ActiveGamepad, GamepadInput};
 use crate::GameState;
 
-mod game_control;
+pub mod bindings;
+pub mod game_control;
+pub mod gamepad;
+
+/// Holding a direction on the stick repeats menu navigation after this many seconds...
+const NAVIGATION_REPEAT_DELAY: f32 = 0.4;
+/// ...and then every this many seconds
+const NAVIGATION_REPEAT_INTERVAL: f32 = 0.15;
+/// How far the stick has to be pushed to navigate menus
+const NAVIGATION_STICK_THRESHOLD: f32 = 0.5;
 
 pub struct ActionsPlugin;
 
-// This plugin listens for keyboard input and converts the input into Actions
+// This plugin listens for keyboard and gamepad input and converts the input into Actions
 // Actions can then be used as a resource in other systems to act on the player input.
+// Which keys map to which action is looked up in the InputBindings resource.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Actions>()
-            .add_system(set_movement_actions.in_set(OnUpdate(GameState::Playing)));
+            .init_resource::<ActiveGamepad>()
+            .insert_resource(InputBindings::load())
+            .add_system(
+                select_active_gamepad
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem),
+            )
+            .add_system(
+                set_menu_actions
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(select_active_gamepad),
+            )
+            .add_system(set_movement_actions.in_set(OnUpdate(GameState::Playing)))
+            .add_system(store_bindings.run_if(resource_changed::<InputBindings>()));
     }
 }
 
 #[derive(Default, Resource)]
 pub struct Actions {
     pub player_movement: Option<Vec2>,
+    pub toggle_pause: bool,
+    pub toggle_journal: bool,
+    pub toggle_dex: bool,
+    pub confirm: bool,
+    pub cancel: bool,
+    /// Index of the move picked with one of the `Move` controls this frame
+    pub selected_move: Option<usize>,
+    /// Direction to move the focus in menus, repeats while a stick is held
+    pub navigation: Option<NavigationDirection>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NavigationDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Next button in tab order
+    Next,
+    Previous,
+}
+
+impl NavigationDirection {
+    const ALL: [(NavigationDirection, GameControl); 4] = [
+        (NavigationDirection::Up, GameControl::Up),
+        (NavigationDirection::Down, GameControl::Down),
+        (NavigationDirection::Left, GameControl::Left),
+        (NavigationDirection::Right, GameControl::Right),
+    ];
+
+    fn from_stick(stick: Vec2) -> Option<Self> {
+        if stick.length() < NAVIGATION_STICK_THRESHOLD {
+            return None;
+        }
+        Some(if stick.x.abs() > stick.y.abs() {
+            if stick.x > 0. {
+                NavigationDirection::Right
+            } else {
+                NavigationDirection::Left
+            }
+        } else if stick.y > 0. {
+            NavigationDirection::Up
+        } else {
+            NavigationDirection::Down
+        })
+    }
 }
 
-pub fn set_movement_actions(mut actions: ResMut<Actions>, keyboard_input: Res<Input<KeyCode>>) {
+/// The stick direction held down for menu navigation and when it repeats next
+#[derive(Default)]
+pub struct StickNavigation {
+    held: Option<NavigationDirection>,
+    repeat_in: f32,
+}
+
+pub fn set_movement_actions(
+    mut actions: ResMut<Actions>,
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: GamepadInput,
+) {
     let player_movement = Vec2::new(
-        get_movement(GameControl::Right, &keyboard_input)
-            - get_movement(GameControl::Left, &keyboard_input),
-        get_movement(GameControl::Up, &keyboard_input)
-            - get_movement(GameControl::Down, &keyboard_input),
+        get_movement(GameControl::Right, &bindings, &keyboard_input)
+            - get_movement(GameControl::Left, &bindings, &keyboard_input),
+        get_movement(GameControl::Up, &bindings, &keyboard_input)
+            - get_movement(GameControl::Down, &bindings, &keyboard_input),
     );
 
     if player_movement != Vec2::ZERO {
         actions.player_movement = Some(player_movement.normalize());
     } else {
-        actions.player_movement = None;
+        actions.player_movement = gamepad_input.movement();
+    }
+}
+
+pub fn set_menu_actions(
+    mut actions: ResMut<Actions>,
+    mut stick_navigation: Local<StickNavigation>,
+    time: Res<Time>,
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: GamepadInput,
+) {
+    let just_pressed = |control: GameControl| {
+        control.just_pressed(&bindings, &keyboard_input) || gamepad_input.just_pressed(control)
+    };
+    actions.toggle_pause = just_pressed(GameControl::Menu);
+    actions.toggle_journal = just_pressed(GameControl::Journal);
+    actions.toggle_dex = just_pressed(GameControl::Dex);
+    actions.confirm = just_pressed(GameControl::Confirm);
+    actions.cancel = just_pressed(GameControl::Cancel);
+    actions.selected_move = GameControl::MOVES
+        .iter()
+        .position(|control| just_pressed(*control));
+
+    actions.navigation = NavigationDirection::ALL
+        .iter()
+        .find(|(_, control)| just_pressed(*control))
+        .map(|(direction, _)| *direction)
+        .or_else(|| tab_navigation(&keyboard_input));
+
+    // menus are also used while the game is paused, so count real time here
+    let stick_direction = gamepad_input
+        .left_stick()
+        .and_then(NavigationDirection::from_stick);
+    if stick_direction != stick_navigation.held {
+        stick_navigation.held = stick_direction;
+        stick_navigation.repeat_in = NAVIGATION_REPEAT_DELAY;
+        actions.navigation = actions.navigation.or(stick_direction);
+    } else if stick_direction.is_some() {
+        stick_navigation.repeat_in -= time.raw_delta_seconds();
+        if stick_navigation.repeat_in <= 0. {
+            stick_navigation.repeat_in += NAVIGATION_REPEAT_INTERVAL;
+            actions.navigation = actions.navigation.or(stick_direction);
+        }
+    }
+}
+
+/// Tab and Shift+Tab walk through the buttons of a menu in tab order
+fn tab_navigation(keyboard_input: &Input<KeyCode>) -> Option<NavigationDirection> {
+    if !keyboard_input.just_pressed(KeyCode::Tab) {
+        return None;
+    }
+    if keyboard_input.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        Some(NavigationDirection::Previous)
+    } else {
+        Some(NavigationDirection::Next)
     }
 }
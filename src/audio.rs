@@ -1,55 +1,45 @@
-use crate::actions::{set_movement_actions, Actions};
-use crate::loading::AudioAssets;
-use crate::GameState;
+use crate::audio::ambient::AmbientPlugin;
+use crate::audio::music::MusicPlugin;
+use crate::audio::sfx::SfxPlugin;
+use crate::settings::Settings;
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
+pub mod ambient;
+pub mod music;
+pub mod sfx;
+pub mod spatial;
+
 pub struct InternalAudioPlugin;
 
 // This plugin is responsible to control the game audio
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
-            .add_system(start_audio.in_schedule(OnEnter(GameState::Playing)))
-            .add_system(
-                control_flying_sound
-                    .after(set_movement_actions)
-                    .in_set(OnUpdate(GameState::Playing)),
-            );
+            .add_plugin(AmbientPlugin)
+            .add_plugin(MusicPlugin)
+            .add_plugin(SfxPlugin)
+            .add_audio_channel::<MusicChannel>()
+            .add_audio_channel::<SfxChannel>()
+            .add_system(apply_volume_settings.run_if(resource_changed::<Settings>()));
     }
 }
 
+/// Channel for background music, its volume follows the music volume setting
 #[derive(Resource)]
-struct FlyingAudio(Handle<AudioInstance>);
+pub struct MusicChannel;
 
-fn start_audio(_commands: Commands, _audio_assets: Res<AudioAssets>, _audio: Res<Audio>) {
-    // audio.pause();
-    // let handle = audio
-    //     .play(audio_assets.flying.clone())
-    //     .looped()
-    //     .with_volume(0.3)
-    //     .handle();
-    // commands.insert_resource(FlyingAudio(handle));
-}
+/// Channel for sound effects, its volume follows the effects volume setting
+#[derive(Resource)]
+pub struct SfxChannel;
 
-fn control_flying_sound(
-    actions: Res<Actions>,
-    audio: Res<FlyingAudio>,
-    mut audio_instances: ResMut<Assets<AudioInstance>>,
+fn apply_volume_settings(
+    settings: Res<Settings>,
+    audio: Res<Audio>,
+    music: Res<AudioChannel<MusicChannel>>,
+    sfx: Res<AudioChannel<SfxChannel>>,
 ) {
-    if let Some(instance) = audio_instances.get_mut(&audio.0) {
-        match instance.state() {
-            PlaybackState::Paused { .. } => {
-                if actions.player_movement.is_some() {
-                    instance.resume(AudioTween::default());
-                }
-            }
-            PlaybackState::Playing { .. } => {
-                if actions.player_movement.is_none() {
-                    instance.pause(AudioTween::default());
-                }
-            }
-            _ => {}
-        }
-    }
+    audio.set_volume(settings.audio.master_volume);
+    music.set_volume(settings.music_volume());
+    sfx.set_volume(settings.sfx_volume());
 }
@@ -0,0 +1,139 @@
+use crate::actions::{set_movement_actions, Actions};
+use crate::audio::spatial::Listener;
+use crate::audio::SfxChannel;
+use crate::loading::AudioAssets;
+use crate::player::Player;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_kira_audio::prelude::*;
+
+pub struct AmbientPlugin;
+
+/// This plugin plays the looped sounds of [`AmbientLoop`] entities, pausing them while their
+/// condition doesn't hold and stopping them once the entity is gone
+/// Loops of entities with a transform are panned and fade with distance to the listener
+impl Plugin for AmbientPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoopInstances>().add_systems(
+            (add_footsteps, start_loops, update_loops, stop_removed_loops)
+                .chain()
+                .after(set_movement_actions),
+        );
+    }
+}
+
+/// A looped sound owned by an entity
+///
+/// The loop only plays in `state` and while `condition` holds, else it is paused.
+#[derive(Component)]
+pub struct AmbientLoop {
+    pub sound: Handle<AudioSource>,
+    pub volume: f64,
+    pub state: GameState,
+    pub condition: fn(&Actions) -> bool,
+}
+
+/// The instance playing for every [`AmbientLoop`] entity
+#[derive(Resource, Default)]
+struct LoopInstances(HashMap<Entity, Handle<AudioInstance>>);
+
+fn player_is_moving(actions: &Actions) -> bool {
+    actions.player_movement.is_some()
+}
+
+fn add_footsteps(
+    mut commands: Commands,
+    audio_assets: Option<Res<AudioAssets>>,
+    players: Query<Entity, Added<Player>>,
+) {
+    let Some(audio_assets) = audio_assets else {
+        return;
+    };
+    for player in &players {
+        commands.entity(player).insert(AmbientLoop {
+            sound: audio_assets.flying.clone(),
+            volume: 0.3,
+            state: GameState::Playing,
+            condition: player_is_moving,
+        });
+    }
+}
+
+fn start_loops(
+    mut instances: ResMut<LoopInstances>,
+    channel: Res<AudioChannel<SfxChannel>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    loops: Query<(Entity, &AmbientLoop), Added<AmbientLoop>>,
+) {
+    for (entity, ambient) in &loops {
+        let instance = channel
+            .play(ambient.sound.clone())
+            .looped()
+            .with_volume(ambient.volume)
+            .paused()
+            .handle();
+        // the entity got a new loop, the old one would keep playing forever
+        if let Some(previous) = instances
+            .0
+            .insert(entity, instance)
+            .and_then(|handle| audio_instances.get_mut(&handle))
+        {
+            previous.stop(AudioTween::default());
+        }
+    }
+}
+
+fn update_loops(
+    instances: Res<LoopInstances>,
+    game_state: Res<State<GameState>>,
+    actions: Option<Res<Actions>>,
+    listener: Listener,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    loops: Query<(Entity, &AmbientLoop, Option<&GlobalTransform>)>,
+) {
+    for (entity, ambient, transform) in &loops {
+        // the instance only shows up once the audio plugin started playing it
+        let Some(instance) = instances
+            .0
+            .get(&entity)
+            .and_then(|handle| audio_instances.get_mut(handle))
+        else {
+            continue;
+        };
+        let active = game_state.0 == ambient.state
+            && actions
+                .as_ref()
+                .is_some_and(|actions| (ambient.condition)(actions));
+        if let (true, Some(transform)) = (active, transform) {
+            let spatial = listener.hear(transform.translation().truncate());
+            instance.set_volume(ambient.volume * spatial.volume, AudioTween::default());
+            instance.set_panning(spatial.panning, AudioTween::default());
+        }
+        match instance.state() {
+            PlaybackState::Paused { .. } if active => {
+                instance.resume(AudioTween::default());
+            }
+            PlaybackState::Playing { .. } if !active => {
+                instance.pause(AudioTween::default());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn stop_removed_loops(
+    mut instances: ResMut<LoopInstances>,
+    mut removed: RemovedComponents<AmbientLoop>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    for entity in removed.iter() {
+        if let Some(instance) = instances
+            .0
+            .remove(&entity)
+            .and_then(|handle| audio_instances.get_mut(&handle))
+        {
+            instance.stop(AudioTween::default());
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::audio::MusicChannel;
+use crate::data::{RonAsset, RonAssetApp};
+use crate::loading::AudioAssets;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy_kira_audio::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How long the old track fades out while the new one fades in
+const CROSSFADE: Duration = Duration::from_millis(800);
+
+pub struct MusicPlugin;
+
+/// This plugin plays a track for every `GameState`, crossfading between them,
+/// and short jingles like the victory fanfare on top, see [`PlayJingle`]
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<MusicTable>()
+            .add_event::<PlayJingle>()
+            .init_resource::<Music>()
+            .add_system(update_music.run_if(resource_exists::<AudioAssets>()));
+    }
+}
+
+/// The tracks of the game, see `assets/audio/base.music.ron`
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "2f6b8d14-93ce-4a57-b1e0-7c4a5d92f386"]
+pub struct MusicTable {
+    pub tracks: BTreeMap<MusicCue, Track>,
+}
+
+impl RonAsset for MusicTable {
+    const EXTENSIONS: &'static [&'static str] = &["music.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.tracks.extend(patch.tracks);
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MusicCue {
+    Title,
+    Overworld,
+    Battle,
+    /// Played once after winning a fight, then the state's track comes back
+    Victory,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Track {
+    /// Asset path of the audio file
+    pub path: String,
+    /// Seconds into the file where the loop starts, the part before is only played once
+    #[serde(default)]
+    pub loop_from: f64,
+    /// Jingles play once instead of looping
+    #[serde(default = "looped_by_default")]
+    pub looped: bool,
+}
+
+fn looped_by_default() -> bool {
+    true
+}
+
+/// Interrupts the music with the cue, which should be a track that doesn't loop
+pub struct PlayJingle(pub MusicCue);
+
+#[derive(Resource, Default)]
+struct Music {
+    cue: Option<MusicCue>,
+    instance: Option<Handle<AudioInstance>>,
+    /// Loaded tracks are kept around, so going back and forth doesn't load them again
+    sources: BTreeMap<MusicCue, Handle<AudioSource>>,
+    jingle: bool,
+}
+
+impl MusicCue {
+    fn for_state(state: &GameState) -> Option<MusicCue> {
+        match state {
+            GameState::Loading => None,
+            GameState::Title => Some(MusicCue::Title),
+            GameState::Playing => Some(MusicCue::Overworld),
+            GameState::Fight => Some(MusicCue::Battle),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_music(
+    mut music: ResMut<Music>,
+    mut jingles: EventReader<PlayJingle>,
+    game_state: Res<State<GameState>>,
+    audio_assets: Res<AudioAssets>,
+    music_tables: Res<Assets<MusicTable>>,
+    asset_server: Res<AssetServer>,
+    channel: Res<AudioChannel<MusicChannel>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    let Some(table) = music_tables.get(&audio_assets.music) else {
+        return;
+    };
+    let mut switch_to = |music: &mut Music, cue: Option<MusicCue>| {
+        if let Some(instance) = music
+            .instance
+            .take()
+            .and_then(|handle| audio_instances.get_mut(&handle))
+        {
+            instance.stop(AudioTween::linear(CROSSFADE));
+        }
+        music.cue = cue;
+        let Some((cue, track)) = cue.and_then(|cue| Some((cue, table.tracks.get(&cue)?))) else {
+            return;
+        };
+        let source = music
+            .sources
+            .entry(cue)
+            .or_insert_with(|| asset_server.load(track.path.as_str()))
+            .clone();
+        let mut play = channel.play(source);
+        play.fade_in(AudioTween::linear(CROSSFADE));
+        if track.looped {
+            play.loop_from(track.loop_from);
+        }
+        music.instance = Some(play.handle());
+    };
+
+    if let Some(PlayJingle(cue)) = jingles.iter().last() {
+        switch_to(&mut music, Some(*cue));
+        music.jingle = true;
+        return;
+    }
+    if music.jingle {
+        // the state's track comes back once the jingle is over
+        let finished = match &music.instance {
+            Some(instance) => matches!(channel.state(instance), PlaybackState::Stopped),
+            None => true,
+        };
+        if !finished {
+            return;
+        }
+        music.jingle = false;
+        music.cue = None;
+    }
+    let cue = MusicCue::for_state(&game_state.0);
+    if music.cue != cue {
+        switch_to(&mut music, cue);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::audio::spatial::{Listener, Spatial};
+use crate::audio::SfxChannel;
+use crate::data::{RonAsset, RonAssetApp};
+use crate::loading::AudioAssets;
+use crate::ui::Disabled;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy_kira_audio::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub struct SfxPlugin;
+
+/// This plugin plays a sound effect for every [`PlaySfx`] event on the [`SfxChannel`]
+/// Every button in the game sounds on hover and click without having to send anything
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<SoundTable>()
+            .add_event::<PlaySfx>()
+            .init_resource::<Voices>()
+            .add_system(button_sounds)
+            .add_system(
+                play_sfx
+                    .after(button_sounds)
+                    .run_if(resource_exists::<AudioAssets>()),
+            );
+    }
+}
+
+/// The sound effects of the game, see `assets/audio/base.sfx.ron`
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "8c1e5a7f-04d2-4b69-9e3a-d57f21b0c6a4"]
+pub struct SoundTable {
+    pub sounds: BTreeMap<Sfx, Sound>,
+}
+
+impl RonAsset for SoundTable {
+    const EXTENSIONS: &'static [&'static str] = &["sfx.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.sounds.extend(patch.sounds);
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Sfx {
+    /// The mouse moved onto a button
+    Hover,
+    Click,
+    Hit,
+    Critical,
+    /// An attack that did no damage
+    Miss,
+    Faint,
+    Capture,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Sound {
+    /// Asset path of the audio file
+    pub path: String,
+    #[serde(default = "full_volume")]
+    pub volume: f64,
+    /// The playback rate is picked at random up to this far from normal speed,
+    /// so repeated sounds don't get tiring
+    #[serde(default)]
+    pub pitch_variation: f64,
+    /// How many instances of the sound may play at once, more are dropped
+    #[serde(default = "default_voices")]
+    pub voices: usize,
+}
+
+fn full_volume() -> f64 {
+    1.0
+}
+
+fn default_voices() -> usize {
+    2
+}
+
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    /// Where in the world the sound comes from, see [`Spatial`]
+    /// Sounds without a position are centered, like the ones of menus
+    pub position: Option<Vec2>,
+}
+
+impl PlaySfx {
+    pub fn new(sfx: Sfx) -> Self {
+        PlaySfx {
+            sfx,
+            position: None,
+        }
+    }
+
+    pub fn at(mut self, position: Vec2) -> Self {
+        self.position = Some(position);
+        self
+    }
+}
+
+/// The instances of every sound that may still be playing
+#[derive(Resource, Default)]
+struct Voices {
+    playing: BTreeMap<Sfx, Vec<Handle<AudioInstance>>>,
+    /// Loaded sounds are kept around, so they are not loaded again for every play
+    sources: BTreeMap<Sfx, Handle<AudioSource>>,
+}
+
+#[allow(clippy::type_complexity)]
+fn button_sounds(
+    mut sfx: EventWriter<PlaySfx>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>, Without<Disabled>)>,
+) {
+    for interaction in &interaction_query {
+        match interaction {
+            Interaction::Hovered => sfx.send(PlaySfx::new(Sfx::Hover)),
+            Interaction::Clicked => sfx.send(PlaySfx::new(Sfx::Click)),
+            Interaction::None => {}
+        }
+    }
+}
+
+fn play_sfx(
+    mut voices: ResMut<Voices>,
+    mut events: EventReader<PlaySfx>,
+    audio_assets: Res<AudioAssets>,
+    sound_tables: Res<Assets<SoundTable>>,
+    asset_server: Res<AssetServer>,
+    channel: Res<AudioChannel<SfxChannel>>,
+    listener: Listener,
+) {
+    let Some(table) = sound_tables.get(&audio_assets.sfx) else {
+        events.clear();
+        return;
+    };
+    for PlaySfx { sfx, position } in events.iter() {
+        let Some(sound) = table.sounds.get(sfx) else {
+            continue;
+        };
+        let spatial = position.map_or(Spatial::CENTERED, |position| listener.hear(position));
+        if !spatial.is_audible() {
+            continue;
+        }
+        let playing = voices.playing.entry(*sfx).or_default();
+        playing.retain(|instance| !matches!(channel.state(instance), PlaybackState::Stopped));
+        if playing.len() >= sound.voices {
+            continue;
+        }
+        let source = voices
+            .sources
+            .entry(*sfx)
+            .or_insert_with(|| asset_server.load(sound.path.as_str()))
+            .clone();
+        let rate = 1.0 + (rand::random::<f64>() * 2.0 - 1.0) * sound.pitch_variation;
+        let instance = channel
+            .play(source)
+            .with_volume(sound.volume * spatial.volume)
+            .with_panning(spatial.panning)
+            .with_playback_rate(rate)
+            .handle();
+        voices.playing.entry(*sfx).or_default().push(instance);
+    }
+}
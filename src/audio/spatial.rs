@@ -0,0 +1,102 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Sounds this close to the listener play at full volume
+const FULL_VOLUME_DISTANCE: f32 = 250.;
+/// Sounds fade out between [`FULL_VOLUME_DISTANCE`] and this distance
+const HEARING_DISTANCE: f32 = 800.;
+/// A sound this far to the side of the listener only comes out of one speaker
+const PAN_DISTANCE: f32 = 400.;
+
+/// Sounds are panned and attenuated relative to the entity with this component
+/// Without one, the listener stands in the middle of the screen like during fights
+#[derive(Component)]
+pub struct SpatialListener;
+
+/// How a sound at some position is heard by the listener
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spatial {
+    /// 0 is left, 0.5 is centered and 1 is right
+    pub panning: f64,
+    /// Multiplier for the sound's own volume
+    pub volume: f64,
+}
+
+impl Spatial {
+    pub const CENTERED: Spatial = Spatial {
+        panning: 0.5,
+        volume: 1.,
+    };
+
+    pub fn new(source: Vec2, listener: Vec2) -> Self {
+        let offset = source - listener;
+        let fade =
+            (offset.length() - FULL_VOLUME_DISTANCE) / (HEARING_DISTANCE - FULL_VOLUME_DISTANCE);
+        Spatial {
+            panning: (0.5 + 0.5 * offset.x / PAN_DISTANCE).clamp(0., 1.) as f64,
+            volume: (1. - fade).clamp(0., 1.) as f64,
+        }
+    }
+
+    pub fn is_audible(&self) -> bool {
+        self.volume > 0.
+    }
+}
+
+#[derive(SystemParam)]
+pub struct Listener<'w, 's> {
+    listeners: Query<'w, 's, &'static GlobalTransform, With<SpatialListener>>,
+}
+
+impl Listener<'_, '_> {
+    pub fn position(&self) -> Vec2 {
+        self.listeners
+            .iter()
+            .next()
+            .map_or(Vec2::ZERO, |transform| transform.translation().truncate())
+    }
+
+    pub fn hear(&self, source: Vec2) -> Spatial {
+        Spatial::new(source, self.position())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LISTENER: Vec2 = Vec2::new(100., -50.);
+
+    fn hear(offset: Vec2) -> Spatial {
+        Spatial::new(LISTENER + offset, LISTENER)
+    }
+
+    #[test]
+    fn close_sounds_play_at_full_volume() {
+        assert_eq!(hear(Vec2::ZERO), Spatial::CENTERED);
+        assert_eq!(hear(Vec2::new(0., 249.)).volume, 1.);
+        assert_eq!(hear(Vec2::new(0., -250.)).volume, 1.);
+        let halfway = hear(Vec2::new(0., 525.)).volume;
+        assert!((halfway - 0.5).abs() < 1e-6, "{halfway}");
+    }
+
+    #[test]
+    fn far_sounds_are_silent() {
+        assert!(!hear(Vec2::new(0., 800.)).is_audible());
+        assert!(!hear(Vec2::new(-900., 0.)).is_audible());
+        assert!(hear(Vec2::new(0., 799.)).is_audible());
+    }
+
+    #[test]
+    fn sounds_to_the_side_are_panned() {
+        assert!(hear(Vec2::new(-100., 0.)).panning < 0.5);
+        assert!(hear(Vec2::new(100., 0.)).panning > 0.5);
+        // only up and down doesn't pan
+        assert_eq!(hear(Vec2::new(0., 300.)).panning, 0.5);
+        assert_eq!(hear(Vec2::new(-200., 0.)).panning, 0.25);
+        assert_eq!(hear(Vec2::new(-400., 0.)).panning, 0.);
+        assert_eq!(hear(Vec2::new(400., 0.)).panning, 1.);
+        assert_eq!(hear(Vec2::new(-700., 0.)).panning, 0.);
+        assert_eq!(hear(Vec2::new(700., 0.)).panning, 1.);
+    }
+}
@@ -1,36 +1,103 @@
-use bevy::prelude::Component;
+use crate::creature::animations::CreatureAnimations;
+use crate::creature::effects::{ActiveEffect, Bonus, Effect, HeldEffect, Malus, Reaction, Trigger};
+use crate::creature::moves::{Move, MoveTable, Roll};
+use crate::creature::species::{Species, SpeciesTable};
+use crate::data::RonAssetApp;
+use crate::inventory::items::Item;
+use bevy::prelude::*;
 use std::f32::consts::PI;
 use uuid::Uuid;
 
-pub mod constants;
+pub mod animations;
+pub mod effects;
+pub mod moves;
+pub mod species;
 
 pub const WIGGLE_MAX_ANGLE: f32 = (10.0 / 360.) * (2.0 * PI);
 
+pub struct CreaturePlugin;
+
+/// This plugin registers the creature data files, like the [`SpeciesTable`] and [`MoveTable`]
+impl Plugin for CreaturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<SpeciesTable>()
+            .add_ron_asset::<MoveTable>()
+            .add_ron_asset::<CreatureAnimations>();
+    }
+}
+
+/// Creatures know at most this many moves, one for each `Move` control
+pub const MAX_MOVES: usize = 4;
+
+/// Critical hits deal this much more damage
+const CRITICAL_FACTOR: f32 = 1.5;
+
 #[derive(Component)]
 pub struct Creature {
     health: u32,
     max_health: u32,
     name: String,
+    level: u32,
+    /// Move ids, see [`MoveTable`]
+    moves: Vec<String>,
+    effects: Vec<ActiveEffect>,
+    held_item: Option<HeldItem>,
     tag: Uuid,
     wiggle_period: f32,
 }
 
+/// The item a creature holds, with everything the fight needs to know about it
+pub struct HeldItem {
+    /// Item id, see [`ItemTable`](crate::inventory::items::ItemTable)
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    effects: Vec<HeldEffect>,
+    /// [`Trigger::LowHealth`] already fired in this fight
+    low_health_fired: bool,
+}
+
 #[derive(Component, Eq, PartialEq)]
 pub struct CreatureTag {
     _tag: Uuid,
 }
 
 impl Creature {
-    pub fn new(name: String) -> Self {
+    pub fn new(species: &Species, level: u32) -> Self {
+        let max_health = species.max_health(level);
         Self {
-            health: 100,
-            max_health: 100,
-            name,
+            health: max_health,
+            max_health,
+            name: species.name.clone(),
+            level,
+            moves: species.moves.iter().take(MAX_MOVES).cloned().collect(),
+            effects: vec![],
+            held_item: None,
             tag: Uuid::new_v4(),
             wiggle_period: f32::max(rand::random::<f32>(), 0.5) + 0.2,
         }
     }
 
+    pub fn with_nickname(mut self, nickname: String) -> Self {
+        self.name = nickname;
+        self
+    }
+
+    pub fn with_moves(mut self, moves: Vec<String>) -> Self {
+        self.moves = moves.into_iter().take(MAX_MOVES).collect();
+        self
+    }
+
+    pub fn with_held_item(mut self, id: &str, item: &Item) -> Self {
+        self.hold(id, item);
+        self
+    }
+
+    pub fn with_health(mut self, health: u32) -> Self {
+        self.health = health.min(self.max_health);
+        self
+    }
+
     pub fn get_creature_tag(&self) -> CreatureTag {
         CreatureTag { _tag: self.tag }
     }
@@ -43,7 +110,228 @@ impl Creature {
         }
     }
 
-    pub fn _is_dead(&self) -> bool {
+    /// Returns how much health was restored
+    pub fn heal(&mut self, health_points: u32) -> u32 {
+        let healed = health_points.min(self.max_health - self.health);
+        self.health += healed;
+        healed
+    }
+
+    /// Rolls the damage of `used_move`, with all bonuses, maluses and the held item applied
+    pub fn roll_damage(&self, used_move: &Move) -> Roll {
+        let mut extra_dice = 0;
+        let mut damage_factor = 1.;
+        let mut extra_damage = 0;
+        for effect in self.held_effects() {
+            if let HeldEffect::Stats { damage, .. } = effect {
+                extra_damage += damage;
+            }
+        }
+        for effect in &self.effects {
+            match effect.effect {
+                Effect::Bonus(Bonus::ExtraDie) => extra_dice += 1,
+                Effect::Malus(Malus::LoseADie) => extra_dice -= 1,
+                Effect::Bonus(Bonus::DoubleDamage) => damage_factor *= 2.,
+                Effect::Malus(Malus::HalfDamage) => damage_factor *= 0.5,
+                Effect::Malus(Malus::LoseHealth(_)) => {}
+            }
+        }
+        let roll = used_move.roll_damage(extra_dice);
+        if roll.critical {
+            damage_factor *= CRITICAL_FACTOR;
+        }
+        let damage = (roll.damage as i32 + extra_damage).max(0);
+        Roll {
+            damage: (damage as f32 * damage_factor) as u32,
+            ..roll
+        }
+    }
+
+    /// `turns` of `None` lasts for the whole fight
+    pub fn add_effect(&mut self, effect: Effect, turns: Option<u32>) {
+        self.effects.push(ActiveEffect { effect, turns });
+    }
+
+    /// Removes all maluses, returns whether there were any
+    /// Maluses of the held item last for as long as it is held
+    pub fn cure(&mut self) -> bool {
+        let before = self.effects.len();
+        self.effects
+            .retain(|active| !matches!(active.effect, Effect::Malus(_)) || active.turns.is_none());
+        self.effects.len() != before
+    }
+
+    /// Gives the creature an item to hold, applying its stats and permanent effects
+    /// Returns the id of the item it held before, which loses its effects
+    pub fn hold(&mut self, id: &str, item: &Item) -> Option<String> {
+        let previous = self.take_held_item();
+        for effect in &item.held {
+            match *effect {
+                HeldEffect::Stats { max_health, .. } => self.change_max_health(max_health),
+                HeldEffect::Permanent(effect) => self.add_effect(effect, None),
+                HeldEffect::Trigger(..) | HeldEffect::OneShot(..) => {}
+            }
+        }
+        self.held_item = Some(HeldItem {
+            id: id.to_string(),
+            name: item.name.clone(),
+            icon: item.icon.clone(),
+            effects: item.held.clone(),
+            low_health_fired: false,
+        });
+        previous
+    }
+
+    /// Takes the held item away and undoes its stats and permanent effects, returns its id
+    pub fn take_held_item(&mut self) -> Option<String> {
+        let held_item = self.held_item.take()?;
+        for effect in &held_item.effects {
+            match *effect {
+                HeldEffect::Stats { max_health, .. } => self.change_max_health(-max_health),
+                HeldEffect::Permanent(effect) => {
+                    let permanent = ActiveEffect {
+                        effect,
+                        turns: None,
+                    };
+                    if let Some(index) = self.effects.iter().position(|active| *active == permanent)
+                    {
+                        self.effects.remove(index);
+                    }
+                }
+                HeldEffect::Trigger(..) | HeldEffect::OneShot(..) => {}
+            }
+        }
+        Some(held_item.id)
+    }
+
+    /// Takes over the stats, moves and held item of `fresh`, built from data reloaded from disk
+    /// The damage taken and the effects gained in the fight stay, and so does a used up item
+    pub fn reload(&mut self, mut fresh: Creature) {
+        if self.held_item.is_none() {
+            fresh.take_held_item();
+        }
+        let lost = self.max_health - self.health;
+        let dead = self.is_dead();
+        let low_health_fired = self
+            .held_item
+            .as_ref()
+            .is_some_and(|held_item| held_item.low_health_fired);
+        // the permanent effects of the old item go, the fresh creature brings the new ones
+        self.take_held_item();
+        self.effects.extend(fresh.effects);
+        self.held_item = fresh.held_item.map(|held_item| HeldItem {
+            low_health_fired,
+            ..held_item
+        });
+        self.max_health = fresh.max_health;
+        self.health = if dead {
+            0
+        } else {
+            self.max_health.saturating_sub(lost).max(1)
+        };
+        self.name = fresh.name;
+        self.level = fresh.level;
+        self.moves = fresh.moves;
+    }
+
+    /// Keeps the damage taken so far, but never knocks the creature out
+    fn change_max_health(&mut self, change: i32) {
+        let lost = self.max_health - self.health;
+        self.max_health = (self.max_health as i32 + change).max(1) as u32;
+        if !self.is_dead() {
+            self.health = self.max_health.saturating_sub(lost).max(1);
+        }
+    }
+
+    fn held_effects(&self) -> impl Iterator<Item = HeldEffect> + '_ {
+        self.held_item
+            .iter()
+            .flat_map(|held_item| held_item.effects.iter().copied())
+    }
+
+    /// Runs the held item's reactions to `trigger`, returns a line for the fight log if any did
+    /// One-shot reactions use the item up, so the creature holds nothing afterwards
+    pub fn trigger_held_item(&mut self, trigger: Trigger) -> Option<String> {
+        if trigger == Trigger::LowHealth {
+            let held_item = self.held_item.as_mut()?;
+            if held_item.low_health_fired || self.health == 0 || self.health * 4 >= self.max_health
+            {
+                return None;
+            }
+            held_item.low_health_fired = true;
+        }
+        let mut used_up = false;
+        let reactions: Vec<Reaction> = self
+            .held_effects()
+            .filter_map(|effect| match effect {
+                HeldEffect::Trigger(fired, reaction) if fired == trigger => Some(reaction),
+                HeldEffect::OneShot(fired, reaction) if fired == trigger => {
+                    used_up = true;
+                    Some(reaction)
+                }
+                _ => None,
+            })
+            .collect();
+        if reactions.is_empty() {
+            return None;
+        }
+        let mut results = vec![];
+        for reaction in reactions {
+            match reaction {
+                Reaction::Heal(amount) => {
+                    let healed = self.heal(amount);
+                    results.push(format!("restored {healed} health"));
+                }
+                Reaction::Gain(effect, turns) => {
+                    self.add_effect(effect, Some(turns));
+                    results.push(format!("made it {}", effect.label()));
+                }
+            }
+        }
+        let item_name = self
+            .held_item
+            .as_ref()
+            .map_or(String::new(), |held_item| held_item.name.clone());
+        if used_up {
+            self.take_held_item();
+            results.push("was used up".to_string());
+        }
+        Some(format!(
+            "{}'s {item_name} {}",
+            self.name,
+            results.join(" and ")
+        ))
+    }
+
+    pub fn get_held_item(&self) -> Option<&HeldItem> {
+        self.held_item.as_ref()
+    }
+
+    pub fn get_effects(&self) -> &[ActiveEffect] {
+        &self.effects
+    }
+
+    /// Applies maluses like poison and counts down effects, returns the damage taken
+    pub fn end_turn(&mut self) -> u32 {
+        let damage: u32 = self
+            .effects
+            .iter()
+            .map(|active| match active.effect {
+                Effect::Malus(Malus::LoseHealth(amount)) => amount,
+                _ => 0,
+            })
+            .sum();
+        self.take_damage(damage);
+        for active in &mut self.effects {
+            if let Some(turns) = &mut active.turns {
+                *turns = turns.saturating_sub(1);
+            }
+        }
+        self.effects.retain(|active| active.turns != Some(0));
+        damage
+    }
+
+    pub fn is_dead(&self) -> bool {
         self.health == 0
     }
 
@@ -59,6 +347,14 @@ impl Creature {
         &self.name
     }
 
+    pub fn get_level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn get_moves(&self) -> &[String] {
+        &self.moves
+    }
+
     pub fn get_wiggle_period(&self) -> f32 {
         self.wiggle_period
     }
@@ -67,3 +363,161 @@ impl Creature {
         self.wiggle_period = new_wiggle_period
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn species(base_health: u32) -> Species {
+        Species {
+            name: "Trashmon".to_string(),
+            description: String::new(),
+            elements: vec![],
+            sprite: String::new(),
+            animations: None,
+            base_health,
+            health_per_level: 0,
+            moves: vec![],
+        }
+    }
+
+    fn item(held: Vec<HeldEffect>) -> Item {
+        Item {
+            name: "Thing".to_string(),
+            description: String::new(),
+            value: 0,
+            effect: None,
+            held,
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn holding_changes_the_max_health_until_taken() {
+        let armor = item(vec![HeldEffect::Stats {
+            max_health: 10,
+            damage: 0,
+        }]);
+        let mut creature = Creature::new(&species(20), 1);
+        creature.hold("armor", &armor);
+        assert_eq!(creature.get_max_health(), 30);
+        assert_eq!(creature.get_health(), 30);
+
+        // the damage taken stays, but the health can't be above the max again
+        creature.take_damage(5);
+        assert_eq!(creature.take_held_item().as_deref(), Some("armor"));
+        assert_eq!(creature.get_max_health(), 20);
+        assert_eq!(creature.get_health(), 15);
+
+        creature.hold("armor", &armor);
+        creature.take_damage(24);
+        creature.take_held_item();
+        assert_eq!(creature.get_health(), 1);
+        assert!(creature.get_held_item().is_none());
+    }
+
+    #[test]
+    fn one_shot_triggers_use_the_item_up() {
+        let snack = item(vec![HeldEffect::OneShot(
+            Trigger::CriticalHit,
+            Reaction::Heal(5),
+        )]);
+        let mut creature = Creature::new(&species(20), 1).with_held_item("snack", &snack);
+        creature.take_damage(10);
+        assert_eq!(
+            creature.trigger_held_item(Trigger::CriticalHit).as_deref(),
+            Some("Trashmon's Thing restored 5 health and was used up")
+        );
+        assert_eq!(creature.get_health(), 15);
+        assert!(creature.get_held_item().is_none());
+        assert_eq!(creature.trigger_held_item(Trigger::CriticalHit), None);
+    }
+
+    #[test]
+    fn low_health_triggers_fire_once() {
+        let charm = item(vec![HeldEffect::Trigger(
+            Trigger::LowHealth,
+            Reaction::Heal(1),
+        )]);
+        let mut creature = Creature::new(&species(20), 1).with_held_item("charm", &charm);
+        creature.take_damage(10);
+        assert_eq!(creature.trigger_held_item(Trigger::LowHealth), None);
+
+        creature.take_damage(6);
+        assert!(creature.trigger_held_item(Trigger::LowHealth).is_some());
+        assert_eq!(creature.get_health(), 5);
+        creature.take_damage(2);
+        assert_eq!(creature.trigger_held_item(Trigger::LowHealth), None);
+        assert!(creature.get_held_item().is_some());
+    }
+
+    #[test]
+    fn reloading_keeps_the_fight_going() {
+        let mut creature = Creature::new(&species(40), 1);
+        creature.take_damage(10);
+        creature.add_effect(Effect::Malus(Malus::LoseADie), Some(2));
+
+        creature.reload(Creature::new(&species(60), 1));
+        assert_eq!(creature.get_max_health(), 60);
+        assert_eq!(creature.get_health(), 50);
+        assert_eq!(
+            creature.get_effects(),
+            [ActiveEffect {
+                effect: Effect::Malus(Malus::LoseADie),
+                turns: Some(2),
+            }]
+        );
+
+        // the damage taken doesn't knock the creature out, but the health fits the new max
+        creature.reload(Creature::new(&species(8), 1));
+        assert_eq!(creature.get_max_health(), 8);
+        assert_eq!(creature.get_health(), 1);
+
+        creature.take_damage(1);
+        creature.reload(Creature::new(&species(60), 1));
+        assert!(creature.is_dead());
+    }
+
+    #[test]
+    fn reloading_keeps_used_up_items_away() {
+        let snack = item(vec![
+            HeldEffect::Stats {
+                max_health: 10,
+                damage: 0,
+            },
+            HeldEffect::OneShot(Trigger::CriticalHit, Reaction::Heal(5)),
+        ]);
+        let mut creature = Creature::new(&species(20), 1).with_held_item("snack", &snack);
+        creature.take_damage(10);
+        creature.trigger_held_item(Trigger::CriticalHit);
+        assert!(creature.get_held_item().is_none());
+
+        // the party member still lists the snack, so the fresh creature holds it again
+        creature.reload(Creature::new(&species(20), 1).with_held_item("snack", &snack));
+        assert!(creature.get_held_item().is_none());
+        assert_eq!(creature.get_max_health(), 20);
+        assert_eq!(creature.get_health(), 15);
+        assert_eq!(creature.trigger_held_item(Trigger::CriticalHit), None);
+    }
+
+    #[test]
+    fn reloading_swaps_the_held_item() {
+        let armor = item(vec![HeldEffect::Stats {
+            max_health: 10,
+            damage: 0,
+        }]);
+        let mut creature = Creature::new(&species(20), 1).with_held_item("armor", &armor);
+        let heavy_armor = item(vec![HeldEffect::Stats {
+            max_health: 20,
+            damage: 0,
+        }]);
+        creature.reload(Creature::new(&species(20), 1).with_held_item("armor", &heavy_armor));
+        assert_eq!(
+            creature
+                .get_held_item()
+                .map(|held_item| held_item.id.as_str()),
+            Some("armor")
+        );
+        assert_eq!(creature.get_max_health(), 40);
+    }
+}
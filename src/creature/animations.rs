@@ -0,0 +1,67 @@
+use crate::data::RonAsset;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// The sprite sheet of a species and the clips played from it in a fight,
+/// see `assets/textures/creatures/gargoylemon.anim.ron`
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "b4d27e90-6c1a-4f53-8e2d-9a07c3f1e6b8"]
+pub struct CreatureAnimations {
+    /// Asset path of the sprite sheet
+    pub sheet: String,
+    /// Width and height of a single frame in pixels
+    pub frame_size: (f32, f32),
+    pub columns: usize,
+    pub rows: usize,
+    pub clips: BTreeMap<AnimationState, Clip>,
+}
+
+impl RonAsset for CreatureAnimations {
+    const EXTENSIONS: &'static [&'static str] = &["anim.ron"];
+}
+
+/// What a creature is doing, the fight switches between these
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AnimationState {
+    Idle,
+    Attack,
+    Hurt,
+    Faint,
+    Victory,
+}
+
+impl AnimationState {
+    /// What follows once a clip that doesn't loop is over, `None` holds its last frame
+    pub fn next(self) -> Option<AnimationState> {
+        match self {
+            AnimationState::Attack | AnimationState::Hurt => Some(AnimationState::Idle),
+            AnimationState::Idle | AnimationState::Faint | AnimationState::Victory => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Clip {
+    /// Indices into the sheet, counted row by row
+    pub frames: Vec<usize>,
+    #[serde(default = "default_fps")]
+    pub fps: f32,
+    #[serde(default)]
+    pub looping: bool,
+    /// Rocks the sprite back and forth while the clip plays
+    #[serde(default)]
+    pub wiggle: bool,
+}
+
+fn default_fps() -> f32 {
+    8.0
+}
+
+impl CreatureAnimations {
+    pub fn clip(&self, state: AnimationState) -> Option<&Clip> {
+        self.clips
+            .get(&state)
+            .filter(|clip| !clip.frames.is_empty())
+    }
+}
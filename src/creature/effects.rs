@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// Something bad sticking to a creature, like a status from a move
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Malus {
+    /// Rolls one die less for every move
+    LoseADie,
+    /// Loses this much health at the end of every turn
+    LoseHealth(u32),
+    HalfDamage,
+}
+
+/// Something good sticking to a creature, like the boost from an item
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bonus {
+    DoubleDamage,
+    /// Rolls one die more for every move
+    ExtraDie,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Malus(Malus),
+    Bonus(Bonus),
+}
+
+/// An effect on a creature and how long it lasts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActiveEffect {
+    pub effect: Effect,
+    /// Turns left, `None` lasts for the whole fight
+    pub turns: Option<u32>,
+}
+
+impl Malus {
+    pub fn label(&self) -> String {
+        match self {
+            Malus::LoseADie => "Weakened".to_string(),
+            Malus::LoseHealth(amount) => format!("Poisoned ({amount})"),
+            Malus::HalfDamage => "Dazed".to_string(),
+        }
+    }
+}
+
+impl Bonus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Bonus::DoubleDamage => "Enraged",
+            Bonus::ExtraDie => "Focused",
+        }
+    }
+}
+
+impl Effect {
+    pub fn label(&self) -> String {
+        match self {
+            Effect::Malus(malus) => malus.label(),
+            Effect::Bonus(bonus) => bonus.label().to_string(),
+        }
+    }
+}
+
+/// What an item does while a creature holds it
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum HeldEffect {
+    /// Changes the holder's stats
+    Stats {
+        #[serde(default)]
+        max_health: i32,
+        /// Added to the damage of every move
+        #[serde(default)]
+        damage: i32,
+    },
+    /// An effect lasting for as long as the item is held
+    Permanent(Effect),
+    /// Reacts every time the trigger happens
+    Trigger(Trigger, Reaction),
+    /// Reacts the first time the trigger happens, which uses the item up
+    OneShot(Trigger, Reaction),
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Health dropped below a quarter, only once per fight
+    LowHealth,
+    /// The holder landed a critical hit
+    CriticalHit,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reaction {
+    Heal(u32),
+    /// Puts the effect on the holder for this many turns
+    Gain(Effect, u32),
+}
@@ -0,0 +1,190 @@
+use crate::creature::effects::Malus;
+use crate::data::RonAsset;
+use bevy::prelude::Color;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Every move a creature can use in a fight, keyed by move id
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "e9ca524f-db53-441a-8429-eaf7c00d99c5"]
+pub struct MoveTable {
+    pub moves: BTreeMap<String, Move>,
+}
+
+impl RonAsset for MoveTable {
+    const EXTENSIONS: &'static [&'static str] = &["moves.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.moves.extend(patch.moves);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Move {
+    pub name: String,
+    /// Number of six sided dice rolled for the damage
+    pub dice: u32,
+    /// Added to the rolled damage
+    #[serde(default)]
+    pub bonus: u32,
+    /// Malus put on the target, and for how many turns
+    #[serde(default)]
+    pub inflicts: Option<(Malus, u32)>,
+    /// Played in order when the move is used, see [`Move::sequence`]
+    #[serde(default)]
+    pub sequence: Vec<Cue>,
+}
+
+/// One step of the animation of a move, the fight waits for each of them
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum Cue {
+    /// The attacker jumps this far towards the target and back
+    Lunge {
+        distance: f32,
+        seconds: f32,
+    },
+    /// Something flies from the attacker to the target,
+    /// the sprite or otherwise a square of the color
+    Projectile {
+        #[serde(default)]
+        sprite: Option<String>,
+        #[serde(default = "white")]
+        color: Color,
+        #[serde(default = "projectile_size")]
+        size: f32,
+        seconds: f32,
+    },
+    /// Bits of the color burst out of the target
+    Particles {
+        color: Color,
+        count: u32,
+        seconds: f32,
+    },
+    /// The target lights up in the color
+    Flash {
+        color: Color,
+        seconds: f32,
+    },
+    /// The target is knocked back this far
+    Recoil {
+        distance: f32,
+        seconds: f32,
+    },
+    /// The damage pops up over the target and its health bar drains
+    Damage {
+        seconds: f32,
+    },
+    Wait(f32),
+}
+
+fn white() -> Color {
+    Color::WHITE
+}
+
+fn projectile_size() -> f32 {
+    24.
+}
+
+impl Cue {
+    pub fn seconds(&self) -> f32 {
+        match self {
+            Cue::Lunge { seconds, .. }
+            | Cue::Projectile { seconds, .. }
+            | Cue::Particles { seconds, .. }
+            | Cue::Flash { seconds, .. }
+            | Cue::Recoil { seconds, .. }
+            | Cue::Damage { seconds }
+            | Cue::Wait(seconds) => *seconds,
+        }
+    }
+
+    /// Only shown if the move hit
+    pub fn is_impact(&self) -> bool {
+        matches!(
+            self,
+            Cue::Particles { .. } | Cue::Flash { .. } | Cue::Recoil { .. }
+        )
+    }
+}
+
+/// The damage a move rolled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Roll {
+    pub damage: u32,
+    /// The first die showed a six
+    pub critical: bool,
+}
+
+impl Move {
+    /// The move's cues, a lunge with a flash and recoil for moves without their own
+    /// A [`Cue::Damage`] is added at the end if the move has none
+    pub fn sequence(&self) -> Vec<Cue> {
+        let mut sequence = if self.sequence.is_empty() {
+            vec![
+                Cue::Lunge {
+                    distance: 60.,
+                    seconds: 0.3,
+                },
+                Cue::Flash {
+                    color: Color::rgb(1., 0.4, 0.4),
+                    seconds: 0.15,
+                },
+                Cue::Recoil {
+                    distance: 25.,
+                    seconds: 0.2,
+                },
+            ]
+        } else {
+            self.sequence.clone()
+        };
+        if !sequence.iter().any(|cue| matches!(cue, Cue::Damage { .. })) {
+            sequence.push(Cue::Damage { seconds: 0.6 });
+        }
+        sequence
+    }
+
+    /// Rolls the move's dice, `extra_dice` more or less of them
+    pub fn roll_damage(&self, extra_dice: i32) -> Roll {
+        let dice = (self.dice as i32 + extra_dice).max(0);
+        let rolled: Vec<u32> = (0..dice).map(|_| rand::random::<u32>() % 6 + 1).collect();
+        Roll {
+            damage: rolled.iter().sum::<u32>() + self.bonus,
+            critical: rolled.first() == Some(&6),
+        }
+    }
+}
+
+impl MoveTable {
+    pub fn get(&self, id: &str) -> Option<&Move> {
+        self.moves.get(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(sequence: Vec<Cue>) -> Move {
+        Move {
+            name: "Scratch".to_string(),
+            dice: 1,
+            bonus: 0,
+            inflicts: None,
+            sequence,
+        }
+    }
+
+    #[test]
+    fn sequences_always_show_the_damage() {
+        let default = scratch(vec![]).sequence();
+        assert!(matches!(default.first(), Some(Cue::Lunge { .. })));
+        assert!(matches!(default.last(), Some(Cue::Damage { .. })));
+
+        let custom = scratch(vec![Cue::Wait(0.5)]).sequence();
+        assert_eq!(custom, [Cue::Wait(0.5), Cue::Damage { seconds: 0.6 }]);
+
+        let damage_first = vec![Cue::Damage { seconds: 0.2 }, Cue::Wait(0.5)];
+        assert_eq!(scratch(damage_first.clone()).sequence(), damage_first);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::data::RonAsset;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Every kind of creature in the game, keyed by species id
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "e7caf573-c0ec-462c-b6a2-dbf64f8d8587"]
+pub struct SpeciesTable {
+    pub species: BTreeMap<String, Species>,
+}
+
+impl RonAsset for SpeciesTable {
+    const EXTENSIONS: &'static [&'static str] = &["species.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.species.extend(patch.species);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Species {
+    pub name: String,
+    /// Shown in the [`Garbagedex`](crate::dex::Garbagedex) once the species was caught
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub elements: Vec<Element>,
+    /// Asset path of the battle sprite
+    pub sprite: String,
+    /// Asset path of the `.anim.ron` file with the battle animations
+    /// Without one, the battle sprite stands still and wiggles
+    #[serde(default)]
+    pub animations: Option<String>,
+    pub base_health: u32,
+    pub health_per_level: u32,
+    /// Move ids known by wild creatures of this species
+    pub moves: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Element {
+    Trash,
+    Metal,
+    Mold,
+    Stone,
+}
+
+impl Element {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Element::Trash => "Trash",
+            Element::Metal => "Metal",
+            Element::Mold => "Mold",
+            Element::Stone => "Stone",
+        }
+    }
+}
+
+impl Species {
+    pub fn max_health(&self, level: u32) -> u32 {
+        self.base_health + self.health_per_level * level
+    }
+}
+
+impl SpeciesTable {
+    pub fn get(&self, id: &str) -> Option<&Species> {
+        self.species.get(id)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::creature::moves::MoveTable;
+use crate::creature::species::SpeciesTable;
+use crate::inventory::items::ItemTable;
+use crate::loading::DataAssets;
+use crate::mods::io::{BASE_PREFIX, MODS_PREFIX};
+use crate::mods::ActiveMods;
+use crate::shop::stock::ShopTable;
+use bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Gameplay data (species, moves, ...) that is written by hand in a RON file
+///
+/// Each kind of data gets its own double extension like `.species.ron`,
+/// so bevy can pick the right loader.
+pub trait RonAsset: TypeUuid + DeserializeOwned + Send + Sync + 'static {
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Combines the file of a content pack with the data loaded before it
+    /// Tables overwrite single entries, everything else is replaced as a whole
+    fn merge(&mut self, patch: Self) {
+        *self = patch;
+    }
+}
+
+pub struct RonLoader<T> {
+    /// Ids of the content packs layered on top of the base files, in load order
+    mods: Vec<String>,
+    _asset: PhantomData<fn() -> T>,
+}
+
+impl<T> FromWorld for RonLoader<T> {
+    fn from_world(world: &mut World) -> Self {
+        let mods = world
+            .get_resource::<ActiveMods>()
+            .map_or_else(Vec::new, |active| {
+                active
+                    .packs
+                    .iter()
+                    .map(|pack| pack.manifest.id.clone())
+                    .collect()
+            });
+        RonLoader {
+            mods,
+            _asset: PhantomData,
+        }
+    }
+}
+
+fn parse<T: RonAsset>(bytes: &[u8], path: &Path) -> Result<T, Error> {
+    ron::de::from_bytes(bytes).map_err(|error| {
+        Error::msg(format!(
+            "{} is invalid and was not loaded: {error}",
+            path.display()
+        ))
+    })
+}
+
+/// Parses every layer of a file and merges them in order, `None` if there are none
+pub(crate) fn merge_layers<T: RonAsset>(layers: &[(PathBuf, Vec<u8>)]) -> Result<Option<T>, Error> {
+    let mut asset: Option<T> = None;
+    for (path, bytes) in layers {
+        let patch = parse(bytes, path)?;
+        match &mut asset {
+            Some(asset) => asset.merge(patch),
+            None => asset = Some(patch),
+        }
+    }
+    Ok(asset)
+}
+
+impl<T: RonAsset> AssetLoader for RonLoader<T> {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            // while hot reloading, the asset keeps its previous version
+            if self.mods.is_empty() {
+                let asset: T = parse(bytes, load_context.path())?;
+                load_context.set_default_asset(LoadedAsset::new(asset));
+                return Ok(());
+            }
+            // with content packs, `bytes` only holds the topmost file,
+            // so every layer is read again and merged on top of the base file
+            let path = load_context.path().to_path_buf();
+            let mut layers = vec![Path::new(BASE_PREFIX).join(&path)];
+            layers.extend(
+                self.mods
+                    .iter()
+                    .map(|id| Path::new(MODS_PREFIX).join(id).join(&path)),
+            );
+            let mut found = vec![];
+            for layer in layers {
+                // a pack only has the files it changes
+                if let Ok(bytes) = load_context.read_asset_bytes(&layer).await {
+                    found.push((layer, bytes));
+                }
+            }
+            let asset = merge_layers::<T>(&found)?
+                .ok_or_else(|| Error::msg(format!("{} was not found", path.display())))?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        T::EXTENSIONS
+    }
+}
+
+pub trait RonAssetApp {
+    /// Registers the asset type together with its loader
+    fn add_ron_asset<T: RonAsset>(&mut self) -> &mut Self;
+}
+
+impl RonAssetApp for App {
+    fn add_ron_asset<T: RonAsset>(&mut self) -> &mut Self {
+        self.add_asset::<T>().init_asset_loader::<RonLoader<T>>()
+    }
+}
+
+/// Read access to the data files, which are loaded in `GameState::Loading`
+#[derive(SystemParam)]
+pub struct GameData<'w> {
+    data_assets: Res<'w, DataAssets>,
+    species_tables: Res<'w, Assets<SpeciesTable>>,
+    move_tables: Res<'w, Assets<MoveTable>>,
+    item_tables: Res<'w, Assets<ItemTable>>,
+    shop_tables: Res<'w, Assets<ShopTable>>,
+}
+
+impl GameData<'_> {
+    pub fn species(&self) -> Option<&SpeciesTable> {
+        self.species_tables.get(&self.data_assets.species)
+    }
+
+    pub fn moves(&self) -> Option<&MoveTable> {
+        self.move_tables.get(&self.data_assets.moves)
+    }
+
+    pub fn items(&self) -> Option<&ItemTable> {
+        self.item_tables.get(&self.data_assets.items)
+    }
+
+    pub fn shops(&self) -> Option<&ShopTable> {
+        self.shop_tables.get(&self.data_assets.shops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(path: &str, ron: &str) -> (PathBuf, Vec<u8>) {
+        (PathBuf::from(path), ron.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn packs_override_single_entries() {
+        let items: ItemTable = merge_layers(&[
+            layer(
+                "base/data/base.items.ron",
+                r#"(items: {
+                    "potion": (name: "Potion", description: "", effect: Some(Heal(20))),
+                    "antidote": (name: "Antidote", description: "", effect: Some(Cure)),
+                })"#,
+            ),
+            layer(
+                "mods/a/data/base.items.ron",
+                r#"(items: {
+                    "potion": (name: "Better Potion", description: "", effect: Some(Heal(30))),
+                })"#,
+            ),
+            layer(
+                "mods/b/data/base.items.ron",
+                r#"(items: {
+                    "candy": (name: "Candy", description: "", effect: Some(Heal(1))),
+                })"#,
+            ),
+        ])
+        .unwrap()
+        .unwrap();
+        let names: Vec<(&str, &str)> = items
+            .items
+            .iter()
+            .map(|(id, item)| (id.as_str(), item.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("antidote", "Antidote"),
+                ("candy", "Candy"),
+                ("potion", "Better Potion"),
+            ]
+        );
+    }
+
+    #[test]
+    fn broken_layers_name_their_file() {
+        assert!(merge_layers::<ShopTable>(&[]).unwrap().is_none());
+        let error = merge_layers::<ShopTable>(&[layer("mods/a/data/base.shops.ron", "(")])
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("mods/a/data/base.shops.ron is invalid"));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::dex::browser::BrowserPlugin;
+use crate::party::PlayerParty;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+pub mod browser;
+
+pub struct DexPlugin;
+
+/// This plugin keeps track of the species the player has seen and caught, see [`Garbagedex`]
+impl Plugin for DexPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(BrowserPlugin).add_system(
+            record_party
+                .run_if(resource_exists::<Garbagedex>())
+                .run_if(resource_exists_and_changed::<PlayerParty>()),
+        );
+    }
+}
+
+/// Species ids the player has met in a fight or caught
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Garbagedex {
+    seen: BTreeSet<String>,
+    caught: BTreeSet<String>,
+}
+
+/// How much the [`Garbagedex`] shows about a species
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Discovery {
+    Unknown,
+    /// Name, sprite, elements and where it lives
+    Seen,
+    /// Everything, including the description and stats
+    Caught,
+}
+
+impl Garbagedex {
+    pub fn see(&mut self, species: &str) {
+        self.seen.insert(species.to_string());
+    }
+
+    pub fn catch(&mut self, species: &str) {
+        self.see(species);
+        self.caught.insert(species.to_string());
+    }
+
+    pub fn discovery(&self, species: &str) -> Discovery {
+        if self.caught.contains(species) {
+            Discovery::Caught
+        } else if self.seen.contains(species) {
+            Discovery::Seen
+        } else {
+            Discovery::Unknown
+        }
+    }
+
+    pub fn seen_count(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn caught_count(&self) -> usize {
+        self.caught.len()
+    }
+}
+
+/// Everything in the party counts as caught, be it the starter or a creature caught in a fight
+fn record_party(party: Res<PlayerParty>, mut dex: ResMut<Garbagedex>) {
+    for member in &party.members {
+        dex.catch(&member.species);
+    }
+}
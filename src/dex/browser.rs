@@ -0,0 +1,297 @@
+use crate::actions::Actions;
+use crate::creature::species::Species;
+use crate::data::GameData;
+use crate::dex::{Discovery, Garbagedex};
+use crate::dialogue::DialogueState;
+use crate::loading::FontAssets;
+use crate::overworld::map::Map;
+use crate::quests::journal::JournalState;
+use crate::shop::ShopState;
+use crate::ui::{spawn_button, ButtonColors, Disabled};
+use crate::{GameState, PauseState};
+use bevy::prelude::*;
+
+pub struct BrowserPlugin;
+
+/// This plugin shows the [`Garbagedex`], an entry for every species that reveals more the better
+/// the player knows it
+/// Like the journal it is opened from the overworld, which is frozen while it is open
+impl Plugin for BrowserPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<DexState>()
+            .configure_set(OnUpdate(GameState::Playing).run_if(in_state(DexState::Closed)))
+            .configure_set(OnUpdate(DexState::Open).run_if(in_state(PauseState::Running)))
+            .add_system(
+                toggle_dex
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(DialogueState::Closed))
+                    .run_if(in_state(JournalState::Closed))
+                    .run_if(in_state(ShopState::Closed))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                (click_entry, draw_dex)
+                    .chain()
+                    .in_set(OnUpdate(DexState::Open)),
+            )
+            .add_system(setup_dex.in_schedule(OnEnter(DexState::Open)))
+            .add_system(cleanup_dex.in_schedule(OnExit(DexState::Open)))
+            .add_system(close_dex.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum DexState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// The entry shown next to the list
+#[derive(Resource, Default)]
+struct DexBrowser {
+    /// Species id
+    selected: Option<String>,
+    redraw: bool,
+}
+
+#[derive(Component)]
+struct DexMenu;
+
+/// Selects the species with this id
+#[derive(Component)]
+struct EntryButton(String);
+
+const TITLE_COLOR: Color = Color::rgb(0.9, 0.75, 0.4);
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const UNKNOWN_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+const BUTTON_SIZE: Size = Size::new(Val::Px(320.0), Val::Px(50.0));
+
+fn toggle_dex(
+    actions: Res<Actions>,
+    dex_state: Res<State<DexState>>,
+    mut next_state: ResMut<NextState<DexState>>,
+) {
+    match dex_state.0 {
+        DexState::Closed if actions.toggle_dex => next_state.set(DexState::Open),
+        DexState::Open if actions.toggle_dex || actions.cancel => next_state.set(DexState::Closed),
+        _ => {}
+    }
+}
+
+fn setup_dex(mut commands: Commands) {
+    commands.insert_resource(DexBrowser {
+        selected: None,
+        redraw: true,
+    });
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(40.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+            z_index: ZIndex::Global(4),
+            ..default()
+        })
+        .insert(DexMenu);
+}
+
+fn click_entry(
+    mut browser: ResMut<DexBrowser>,
+    interaction_query: Query<(&Interaction, &EntryButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            browser.selected = Some(button.0.clone());
+            browser.redraw = true;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_dex(
+    mut commands: Commands,
+    mut browser: ResMut<DexBrowser>,
+    dex: Res<Garbagedex>,
+    game_data: GameData,
+    maps: Res<Assets<Map>>,
+    asset_server: Res<AssetServer>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    menu: Query<Entity, With<DexMenu>>,
+) {
+    if !browser.redraw {
+        return;
+    }
+    let Some(species_table) = game_data.species() else {
+        return;
+    };
+    browser.redraw = false;
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.fira_sans.clone(),
+                font_size,
+                color,
+            },
+        )
+        .with_style(Style {
+            margin: UiRect::bottom(Val::Px(6.0)),
+            max_size: Size::width(Val::Px(600.0)),
+            ..default()
+        })
+    };
+    let column = NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            margin: UiRect::right(Val::Px(40.0)),
+            ..default()
+        },
+        ..default()
+    };
+    let selected = browser
+        .selected
+        .as_ref()
+        .and_then(|id| Some((id, species_table.get(id)?)));
+    for entity in &menu {
+        let mut menu = commands.entity(entity);
+        menu.despawn_descendants();
+        menu.with_children(|parent| {
+            parent.spawn(text("Garbagedex".to_string(), 50.0, TEXT_COLOR));
+            parent.spawn(text(
+                format!(
+                    "Seen {}, caught {} of {}",
+                    dex.seen_count(),
+                    dex.caught_count(),
+                    species_table.species.len()
+                ),
+                22.0,
+                TEXT_COLOR,
+            ));
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                parent.spawn(column.clone()).with_children(|parent| {
+                    for (number, (id, species)) in species_table.species.iter().enumerate() {
+                        let discovery = dex.discovery(id);
+                        let name = match discovery {
+                            Discovery::Unknown => "???",
+                            Discovery::Seen | Discovery::Caught => species.name.as_str(),
+                        };
+                        let mut button = spawn_button(
+                            parent,
+                            &format!("{:03} {name}", number + 1),
+                            font_assets.fira_sans.clone(),
+                            &button_colors,
+                            BUTTON_SIZE,
+                        );
+                        button.insert(EntryButton(id.clone()));
+                        if discovery == Discovery::Unknown {
+                            button.insert(Disabled);
+                        }
+                    }
+                });
+                parent.spawn(column.clone()).with_children(|parent| {
+                    let Some((id, species)) = selected else {
+                        parent.spawn(text(
+                            "Pick a species to read about it".to_string(),
+                            22.0,
+                            UNKNOWN_COLOR,
+                        ));
+                        return;
+                    };
+                    parent.spawn(ImageBundle {
+                        style: Style {
+                            size: Size::all(Val::Px(128.0)),
+                            margin: UiRect::bottom(Val::Px(10.0)),
+                            ..default()
+                        },
+                        image: UiImage::new(asset_server.load(species.sprite.as_str())),
+                        ..default()
+                    });
+                    parent.spawn(text(species.name.clone(), 32.0, TITLE_COLOR));
+                    for line in entry_lines(id, species, dex.discovery(id), &game_data, &maps) {
+                        parent.spawn(text(line, 22.0, TEXT_COLOR));
+                    }
+                    if dex.discovery(id) != Discovery::Caught {
+                        parent.spawn(text(
+                            "Catch one to learn more".to_string(),
+                            22.0,
+                            UNKNOWN_COLOR,
+                        ));
+                    }
+                });
+            });
+        });
+    }
+}
+
+/// The lines shown for a species, more of them once it was caught
+fn entry_lines(
+    id: &str,
+    species: &Species,
+    discovery: Discovery,
+    game_data: &GameData,
+    maps: &Assets<Map>,
+) -> Vec<String> {
+    let mut lines = vec![];
+    if !species.elements.is_empty() {
+        let elements: Vec<&str> = species
+            .elements
+            .iter()
+            .map(|element| element.label())
+            .collect();
+        lines.push(format!("Elements: {}", elements.join(", ")));
+    }
+    // only maps that are loaded, which always includes the current one
+    let mut habitats: Vec<&str> = maps
+        .iter()
+        .filter(|(_, map)| {
+            map.encounters.iter().any(|zone| {
+                zone.species
+                    .iter()
+                    .any(|(zone_species, _)| zone_species == id)
+            })
+        })
+        .map(|(_, map)| map.name.as_str())
+        .collect();
+    habitats.sort_unstable();
+    habitats.dedup();
+    if !habitats.is_empty() {
+        lines.push(format!("Found in: {}", habitats.join(", ")));
+    }
+    if discovery == Discovery::Caught {
+        lines.push(species.description.clone());
+        lines.push(format!(
+            "Health: {} + {} per level",
+            species.base_health, species.health_per_level
+        ));
+        let moves: Vec<&str> = species
+            .moves
+            .iter()
+            .map(|move_id| {
+                game_data
+                    .moves()
+                    .and_then(|moves| moves.get(move_id))
+                    .map_or(move_id.as_str(), |known_move| known_move.name.as_str())
+            })
+            .collect();
+        lines.push(format!("Moves: {}", moves.join(", ")));
+    }
+    lines
+}
+
+/// Quitting to the title screen with the Garbagedex open
+fn close_dex(mut next_state: ResMut<NextState<DexState>>) {
+    next_state.set(DexState::Closed);
+}
+
+fn cleanup_dex(mut commands: Commands, menu: Query<Entity, With<DexMenu>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<DexBrowser>();
+}
@@ -0,0 +1,677 @@
+use crate::actions::Actions;
+use crate::data::RonAssetApp;
+use crate::dialogue::script::{Choice, DialogueAction, DialogueNode, DialogueScript, Next};
+use crate::fight_stage::PendingBattle;
+use crate::flags::{FlagChange, Flags};
+use crate::inventory::Inventory;
+use crate::loading::{DialogueAssets, FontAssets};
+use crate::party::PlayerParty;
+use crate::quests::Quests;
+use crate::settings::Settings;
+use crate::shop::ActiveShop;
+use crate::ui::{spawn_button, ButtonColors};
+use crate::{GameState, PauseState};
+use bevy::prelude::*;
+
+pub mod script;
+
+pub struct DialoguePlugin;
+
+/// This plugin shows what npcs and signs have to say in a box at the bottom of the screen
+/// The overworld is frozen while the box is open, confirm moves on to the next line
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<DialogueScript>()
+            .add_state::<DialogueState>()
+            .add_event::<StartDialogue>()
+            .configure_set(OnUpdate(GameState::Playing).run_if(in_state(DialogueState::Closed)))
+            .configure_set(OnUpdate(DialogueState::Open).run_if(in_state(PauseState::Running)))
+            .add_system(start_dialogue.run_if(in_state(DialogueState::Closed)))
+            .add_systems(
+                (
+                    advance_dialogue,
+                    click_choice,
+                    type_text.after(advance_dialogue).after(click_choice),
+                    draw_dialogue_box.after(type_text),
+                )
+                    .in_set(OnUpdate(DialogueState::Open)),
+            )
+            .add_system(cleanup_dialogue_box.in_schedule(OnExit(DialogueState::Open)))
+            .add_system(close_dialogue.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum DialogueState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// Opens the dialogue box
+pub struct StartDialogue {
+    pub source: DialogueSource,
+    /// What happens after the dialogue is over
+    pub then: Option<DialogueOutcome>,
+}
+
+pub enum DialogueSource {
+    /// A few lines without any script behind them, `None` for narration
+    Lines {
+        speaker: Option<String>,
+        lines: Vec<String>,
+    },
+    /// Id of the [`DialogueNode`] to start with
+    Script(String),
+}
+
+#[derive(Clone)]
+pub enum DialogueOutcome {
+    Fight(PendingBattle),
+    /// Shop id, see [`ActiveShop`]
+    Shop(String),
+}
+
+impl StartDialogue {
+    pub fn lines(speaker: Option<&str>, lines: Vec<String>) -> Self {
+        StartDialogue {
+            source: DialogueSource::Lines {
+                speaker: speaker.map(str::to_string),
+                lines,
+            },
+            then: None,
+        }
+    }
+
+    pub fn script(node: &str) -> Self {
+        StartDialogue {
+            source: DialogueSource::Script(node.to_string()),
+            then: None,
+        }
+    }
+
+    pub fn then(mut self, outcome: DialogueOutcome) -> Self {
+        self.then = Some(outcome);
+        self
+    }
+}
+
+#[derive(Resource)]
+struct Dialogue {
+    node: DialogueNode,
+    /// Index of the line in `node` being shown
+    line: usize,
+    /// How many characters of the line are shown so far
+    shown: f32,
+    /// Offered once the last line of the node was read
+    choices: Vec<Choice>,
+    then: Option<DialogueOutcome>,
+    /// The box needs to be rebuilt for a new speaker or choices
+    redraw: bool,
+}
+
+impl Dialogue {
+    fn new(node: DialogueNode, then: Option<DialogueOutcome>) -> Self {
+        Dialogue {
+            node,
+            line: 0,
+            shown: 0.,
+            choices: vec![],
+            then,
+            redraw: true,
+        }
+    }
+
+    fn enter(&mut self, node: DialogueNode) {
+        self.node = node;
+        self.line = 0;
+        self.shown = 0.;
+        self.choices.clear();
+        self.redraw = true;
+    }
+
+    fn current_line(&self) -> &str {
+        self.node
+            .lines
+            .get(self.line)
+            .map_or("", |line| line.as_str())
+    }
+
+    fn line_is_complete(&self) -> bool {
+        self.shown as usize >= self.current_line().chars().count()
+    }
+
+    /// Shows more of the current line, `None` shows all of it at once
+    fn reveal(&mut self, characters_per_second: Option<f32>, delta: f32) {
+        self.shown = match characters_per_second {
+            Some(speed) => self.shown + speed * delta,
+            None => f32::INFINITY,
+        };
+    }
+
+    /// The part of the current line shown so far
+    fn shown_text(&self) -> String {
+        let shown = self.shown.min(usize::MAX as f32) as usize;
+        self.current_line().chars().take(shown).collect()
+    }
+
+    /// Runs the actions of the current node, battles and shops wait for the dialogue to end
+    fn run_actions(
+        &mut self,
+        flags: &mut Flags,
+        quests: &mut Quests,
+        inventory: &mut Inventory,
+        party: &mut PlayerParty,
+    ) {
+        for action in self.node.actions.clone() {
+            match action {
+                DialogueAction::SetFlag(flag) => flags.apply(&FlagChange::Set(flag)),
+                DialogueAction::ClearFlag(flag) => flags.apply(&FlagChange::Clear(flag)),
+                DialogueAction::ChangeFlag(change) => flags.apply(&change),
+                DialogueAction::StartQuest(quest) => quests.start(&quest),
+                DialogueAction::GiveItem(item, count) => inventory.add(&item, count),
+                DialogueAction::HealParty => party.heal(),
+                DialogueAction::StartBattle(opponents) => {
+                    self.then = Some(DialogueOutcome::Fight(PendingBattle {
+                        opponents,
+                        trainer: None,
+                        on_win: vec![],
+                    }));
+                }
+                DialogueAction::OpenShop(shop) => {
+                    self.then = Some(DialogueOutcome::Shop(shop));
+                }
+            }
+        }
+    }
+
+    /// Continues with `next`, returns `false` once the dialogue is over
+    /// `nodes` looks up a node by its id, see [`ScriptLookup`]
+    fn follow(
+        &mut self,
+        next: &Next,
+        nodes: &impl Fn(&str) -> Option<DialogueNode>,
+        flags: &Flags,
+        quests: &Quests,
+    ) -> bool {
+        match next {
+            Next::End => false,
+            Next::Goto(id) => match nodes(id) {
+                Some(node) => {
+                    self.enter(node);
+                    true
+                }
+                None => {
+                    warn!("Unknown dialogue node \"{id}\"");
+                    false
+                }
+            },
+            Next::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let id = if condition.is_met(flags, quests) {
+                    then
+                } else {
+                    otherwise
+                };
+                self.follow(&Next::Goto(id.clone()), nodes, flags, quests)
+            }
+            Next::Choices(choices) => {
+                self.choices = choices
+                    .iter()
+                    .filter(|choice| {
+                        choice
+                            .condition
+                            .iter()
+                            .all(|condition| condition.is_met(flags, quests))
+                    })
+                    .cloned()
+                    .collect();
+                self.redraw = true;
+                !self.choices.is_empty()
+            }
+        }
+    }
+}
+
+/// Finds dialogue nodes in all loaded scripts
+struct ScriptLookup<'a> {
+    assets: &'a DialogueAssets,
+    scripts: &'a Assets<DialogueScript>,
+}
+
+impl ScriptLookup<'_> {
+    fn node(&self, id: &str) -> Option<DialogueNode> {
+        self.assets
+            .scripts
+            .iter()
+            .filter_map(|handle| self.scripts.get(handle))
+            .find_map(|script| script.nodes.get(id))
+            .cloned()
+    }
+}
+
+#[derive(Component)]
+struct DialogueBox;
+
+#[derive(Component)]
+struct DialogueText;
+
+/// Picks the choice with this index in [`Dialogue::choices`]
+#[derive(Component)]
+struct ChoiceButton(usize);
+
+fn start_dialogue(
+    mut commands: Commands,
+    mut events: EventReader<StartDialogue>,
+    mut next_state: ResMut<NextState<DialogueState>>,
+    dialogue_assets: Res<DialogueAssets>,
+    scripts: Res<Assets<DialogueScript>>,
+) {
+    // only one dialogue at a time, the first one wins
+    let Some(event) = events.iter().next() else {
+        return;
+    };
+    let node = match &event.source {
+        DialogueSource::Lines { speaker, lines } => Some(DialogueNode {
+            speaker: speaker.clone(),
+            lines: lines.clone(),
+            ..default()
+        }),
+        DialogueSource::Script(id) => {
+            let lookup = ScriptLookup {
+                assets: &dialogue_assets,
+                scripts: &scripts,
+            };
+            let node = lookup.node(id);
+            if node.is_none() {
+                warn!("Unknown dialogue node \"{id}\"");
+            }
+            node
+        }
+    };
+    if let Some(node) = node {
+        commands.insert_resource(Dialogue::new(node, event.then.clone()));
+        next_state.set(DialogueState::Open);
+    }
+    events.clear();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_dialogue(
+    mut commands: Commands,
+    actions: Res<Actions>,
+    dialogue_assets: Res<DialogueAssets>,
+    scripts: Res<Assets<DialogueScript>>,
+    mut dialogue: ResMut<Dialogue>,
+    mut flags: ResMut<Flags>,
+    mut quests: ResMut<Quests>,
+    mut inventory: ResMut<Inventory>,
+    mut party: ResMut<PlayerParty>,
+    mut next_dialogue_state: ResMut<NextState<DialogueState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    // choices are picked with their buttons
+    if !actions.confirm || !dialogue.choices.is_empty() {
+        return;
+    }
+    if !dialogue.line_is_complete() {
+        dialogue.shown = f32::INFINITY;
+        return;
+    }
+    if dialogue.line + 1 < dialogue.node.lines.len() {
+        dialogue.line += 1;
+        dialogue.shown = 0.;
+        return;
+    }
+    dialogue.run_actions(&mut flags, &mut quests, &mut inventory, &mut party);
+    let lookup = ScriptLookup {
+        assets: &dialogue_assets,
+        scripts: &scripts,
+    };
+    let next = dialogue.node.next.clone();
+    if !dialogue.follow(&next, &|id| lookup.node(id), &flags, &quests) {
+        finish(
+            &mut commands,
+            &mut dialogue,
+            &mut next_dialogue_state,
+            &mut next_game_state,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn click_choice(
+    mut commands: Commands,
+    dialogue_assets: Res<DialogueAssets>,
+    scripts: Res<Assets<DialogueScript>>,
+    flags: Res<Flags>,
+    quests: Res<Quests>,
+    mut dialogue: ResMut<Dialogue>,
+    mut next_dialogue_state: ResMut<NextState<DialogueState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<(&Interaction, &ChoiceButton), Changed<Interaction>>,
+) {
+    let Some(index) = interaction_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| button.0)
+    else {
+        return;
+    };
+    let Some(choice) = dialogue.choices.get(index).cloned() else {
+        return;
+    };
+    let lookup = ScriptLookup {
+        assets: &dialogue_assets,
+        scripts: &scripts,
+    };
+    let next = choice.goto.map_or(Next::End, Next::Goto);
+    if !dialogue.follow(&next, &|id| lookup.node(id), &flags, &quests) {
+        finish(
+            &mut commands,
+            &mut dialogue,
+            &mut next_dialogue_state,
+            &mut next_game_state,
+        );
+    }
+}
+
+fn finish(
+    commands: &mut Commands,
+    dialogue: &mut Dialogue,
+    next_dialogue_state: &mut NextState<DialogueState>,
+    next_game_state: &mut NextState<GameState>,
+) {
+    next_dialogue_state.set(DialogueState::Closed);
+    match dialogue.then.take() {
+        Some(DialogueOutcome::Fight(battle)) => {
+            commands.insert_resource(battle);
+            next_game_state.set(GameState::Fight);
+        }
+        Some(DialogueOutcome::Shop(shop)) => commands.insert_resource(ActiveShop::new(&shop)),
+        None => {}
+    }
+}
+
+/// Reveals the current line character by character, as fast as the text speed setting says
+fn type_text(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut dialogue: ResMut<Dialogue>,
+    mut text_query: Query<&mut Text, With<DialogueText>>,
+) {
+    let speed = settings.gameplay.text_speed.characters_per_second();
+    dialogue.reveal(speed, time.delta_seconds());
+    let line = dialogue.shown_text();
+    for mut text in &mut text_query {
+        if text.sections[0].value != line {
+            text.sections[0].value = line.clone();
+        }
+    }
+}
+
+fn draw_dialogue_box(
+    mut commands: Commands,
+    mut dialogue: ResMut<Dialogue>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    asset_server: Res<AssetServer>,
+    dialogue_box: Query<Entity, With<DialogueBox>>,
+) {
+    if !dialogue.redraw {
+        return;
+    }
+    dialogue.redraw = false;
+    for entity in &dialogue_box {
+        commands.entity(entity).despawn_recursive();
+    }
+    let text_style = TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: 28.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                min_size: Size::height(Val::Px(160.0)),
+                position_type: PositionType::Absolute,
+                position: UiRect::bottom(Val::Px(0.0)),
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.05, 0.05, 0.1, 0.9).into(),
+            z_index: ZIndex::Global(5),
+            ..default()
+        })
+        .insert(DialogueBox)
+        .with_children(|parent| {
+            if let Some(portrait) = &dialogue.node.portrait {
+                parent.spawn(ImageBundle {
+                    style: Style {
+                        size: Size::all(Val::Px(120.0)),
+                        margin: UiRect::right(Val::Px(20.0)),
+                        ..default()
+                    },
+                    image: asset_server.load(portrait.as_str()).into(),
+                    ..default()
+                });
+            }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        flex_grow: 1.,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    if let Some(speaker) = &dialogue.node.speaker {
+                        parent.spawn(TextBundle::from_section(
+                            speaker.clone(),
+                            TextStyle {
+                                color: Color::rgb(0.9, 0.75, 0.4),
+                                ..text_style.clone()
+                            },
+                        ));
+                    }
+                    parent
+                        .spawn(TextBundle::from_section(String::new(), text_style))
+                        .insert(DialogueText);
+                    for (index, choice) in dialogue.choices.iter().enumerate() {
+                        spawn_button(
+                            parent,
+                            &choice.text,
+                            font_assets.fira_sans.clone(),
+                            &button_colors,
+                            Size::new(Val::Px(400.0), Val::Px(45.0)),
+                        )
+                        .insert(ChoiceButton(index));
+                    }
+                });
+        });
+}
+
+/// Quitting to the title screen in the middle of a conversation
+fn close_dialogue(mut next_state: ResMut<NextState<DialogueState>>) {
+    next_state.set(DialogueState::Closed);
+}
+
+fn cleanup_dialogue_box(mut commands: Commands, dialogue_box: Query<Entity, With<DialogueBox>>) {
+    commands.remove_resource::<Dialogue>();
+    for entity in &dialogue_box {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::Condition;
+    use crate::party::PartyMember;
+    use crate::settings::TextSpeed;
+    use std::collections::BTreeMap;
+
+    fn node(line: &str, next: Next) -> DialogueNode {
+        DialogueNode {
+            lines: vec![line.to_string()],
+            next,
+            ..default()
+        }
+    }
+
+    fn script() -> BTreeMap<String, DialogueNode> {
+        [
+            ("thanks", node("Thank you!", Next::End)),
+            ("offer", node("Could you help me?", Next::End)),
+        ]
+        .into_iter()
+        .map(|(id, node)| (id.to_string(), node))
+        .collect()
+    }
+
+    fn follow(dialogue: &mut Dialogue, next: Next, flags: &Flags, quests: &Quests) -> bool {
+        let nodes = script();
+        dialogue.follow(&next, &|id| nodes.get(id).cloned(), flags, quests)
+    }
+
+    fn choice(text: &str, condition: Option<Condition>) -> Choice {
+        Choice {
+            text: text.to_string(),
+            goto: None,
+            condition,
+        }
+    }
+
+    #[test]
+    fn goto_enters_the_node() {
+        let mut dialogue = Dialogue::new(node("Hello", Next::End), None);
+        dialogue.shown = 3.;
+        let (flags, quests) = (Flags::default(), Quests::default());
+        assert!(follow(
+            &mut dialogue,
+            Next::Goto("thanks".to_string()),
+            &flags,
+            &quests
+        ));
+        assert_eq!(dialogue.current_line(), "Thank you!");
+        assert_eq!(dialogue.shown, 0.);
+
+        assert!(!follow(
+            &mut dialogue,
+            Next::Goto("missing".to_string()),
+            &flags,
+            &quests
+        ));
+        assert!(!follow(&mut dialogue, Next::End, &flags, &quests));
+    }
+
+    #[test]
+    fn branches_check_their_condition() {
+        let branch = || Next::Branch {
+            condition: Condition::Flag("helped".to_string()),
+            then: "thanks".to_string(),
+            otherwise: "offer".to_string(),
+        };
+        let mut flags = Flags::default();
+        let quests = Quests::default();
+        let mut dialogue = Dialogue::new(node("Hello", Next::End), None);
+        assert!(follow(&mut dialogue, branch(), &flags, &quests));
+        assert_eq!(dialogue.current_line(), "Could you help me?");
+
+        flags.apply(&FlagChange::Set("helped".to_string()));
+        assert!(follow(&mut dialogue, branch(), &flags, &quests));
+        assert_eq!(dialogue.current_line(), "Thank you!");
+    }
+
+    #[test]
+    fn unmet_choices_are_hidden() {
+        let choices = Next::Choices(vec![
+            choice("Bye", None),
+            choice("Any quest?", Some(Condition::NotFlag("helped".to_string()))),
+            choice("Thanks again", Some(Condition::Flag("helped".to_string()))),
+        ]);
+        let mut flags = Flags::default();
+        let quests = Quests::default();
+        let mut dialogue = Dialogue::new(node("Hello", Next::End), None);
+        assert!(follow(&mut dialogue, choices.clone(), &flags, &quests));
+        let texts: Vec<&str> = dialogue
+            .choices
+            .iter()
+            .map(|offered| offered.text.as_str())
+            .collect();
+        assert_eq!(texts, ["Bye", "Any quest?"]);
+
+        flags.apply(&FlagChange::Set("helped".to_string()));
+        assert!(follow(&mut dialogue, choices, &flags, &quests));
+        let texts: Vec<&str> = dialogue
+            .choices
+            .iter()
+            .map(|offered| offered.text.as_str())
+            .collect();
+        assert_eq!(texts, ["Bye", "Thanks again"]);
+
+        // without anything to pick, the dialogue is over
+        let hidden = Next::Choices(vec![choice(
+            "Never",
+            Some(Condition::Flag("unknown".to_string())),
+        )]);
+        assert!(!follow(&mut dialogue, hidden, &flags, &quests));
+    }
+
+    #[test]
+    fn actions_change_the_game() {
+        let mut node = node("Take this", Next::End);
+        node.actions = vec![
+            DialogueAction::SetFlag("met".to_string()),
+            DialogueAction::ChangeFlag(FlagChange::Add("visits".to_string(), 2)),
+            DialogueAction::ClearFlag("lost".to_string()),
+            DialogueAction::StartQuest("beach".to_string()),
+            DialogueAction::GiveItem("potion".to_string(), 2),
+            DialogueAction::HealParty,
+            DialogueAction::OpenShop("corner_store".to_string()),
+        ];
+        let mut flags = Flags::default();
+        flags.apply(&FlagChange::Set("lost".to_string()));
+        let mut quests = Quests::default();
+        let mut inventory = Inventory {
+            items: BTreeMap::new(),
+            money: 0,
+        };
+        let mut hurt = PartyMember::wild("binrat", 3);
+        hurt.health = Some(1);
+        let mut party = PlayerParty {
+            members: vec![hurt],
+        };
+
+        let mut dialogue = Dialogue::new(node, None);
+        dialogue.run_actions(&mut flags, &mut quests, &mut inventory, &mut party);
+        assert!(flags.is_set("met"));
+        assert_eq!(flags.get("visits"), 2);
+        assert!(!flags.is_set("lost"));
+        assert!(quests.is_active("beach"));
+        assert_eq!(inventory.items.get("potion"), Some(&2));
+        assert_eq!(party.members[0].health, None);
+        assert!(matches!(
+            dialogue.then,
+            Some(DialogueOutcome::Shop(ref shop)) if shop == "corner_store"
+        ));
+    }
+
+    #[test]
+    fn instant_text_shows_the_whole_line() {
+        let mut dialogue = Dialogue::new(node("Hello there", Next::End), None);
+        dialogue.reveal(Some(10.), 0.5);
+        assert_eq!(dialogue.shown_text(), "Hello");
+        assert!(!dialogue.line_is_complete());
+
+        assert_eq!(TextSpeed::Instant.characters_per_second(), None);
+        dialogue.reveal(TextSpeed::Instant.characters_per_second(), 0.);
+        assert_eq!(dialogue.shown_text(), "Hello there");
+        assert!(dialogue.line_is_complete());
+    }
+}
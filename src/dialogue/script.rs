@@ -0,0 +1,81 @@
+use crate::data::RonAsset;
+use crate::flags::{Condition, FlagChange};
+use crate::party::PartyMember;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Dialogue nodes keyed by node id
+///
+/// Npcs, signs and scripts refer to a node id, the conversation starts there.
+/// Node ids are shared between all loaded scripts.
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "3f0d6b1c-8a43-4c1e-9d7a-5b2e6f1a0c94"]
+pub struct DialogueScript {
+    pub nodes: BTreeMap<String, DialogueNode>,
+}
+
+impl RonAsset for DialogueScript {
+    const EXTENSIONS: &'static [&'static str] = &["dialogue.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.nodes.extend(patch.nodes);
+    }
+}
+
+/// A few lines by one speaker, followed by whatever `next` says
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DialogueNode {
+    /// `None` for signs and narration
+    #[serde(default)]
+    pub speaker: Option<String>,
+    /// Asset path of an image shown next to the text
+    #[serde(default)]
+    pub portrait: Option<String>,
+    pub lines: Vec<String>,
+    /// Run after the last line
+    #[serde(default)]
+    pub actions: Vec<DialogueAction>,
+    #[serde(default)]
+    pub next: Next,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub enum Next {
+    #[default]
+    End,
+    Goto(String),
+    /// Lets the player pick, choices whose condition isn't met are hidden
+    Choices(Vec<Choice>),
+    /// Continues with `then` if the condition is met, otherwise with `otherwise`
+    Branch {
+        condition: Condition,
+        then: String,
+        otherwise: String,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Choice {
+    pub text: String,
+    /// `None` ends the dialogue
+    #[serde(default)]
+    pub goto: Option<String>,
+    #[serde(default)]
+    pub condition: Option<Condition>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum DialogueAction {
+    SetFlag(String),
+    ClearFlag(String),
+    ChangeFlag(FlagChange),
+    StartQuest(String),
+    /// Item id and count
+    GiveItem(String, u32),
+    HealParty,
+    /// Fights the given creatures once the dialogue is over
+    StartBattle(Vec<PartyMember>),
+    /// Opens the shop with this id once the dialogue is over
+    OpenShop(String),
+}
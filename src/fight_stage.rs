@@ -1,50 +1,189 @@
-use crate::creature::{Creature, CreatureTag, WIGGLE_MAX_ANGLE};
-use crate::loading::{FontAssets, TextureAssets};
+use crate::creature::{Creature, CreatureTag};
+use crate::data::GameData;
+use crate::dex::Garbagedex;
+use crate::fight_stage::action_bar::{
+    click_action_button, describe_hovered_item, draw_action_bar, ActionBar, BarMenu,
+    ItemDescription,
+};
+use crate::fight_stage::animation::{
+    animate_creatures, creature_sprite, use_sprite_sheets, wiggle_spriteses,
+};
+use crate::fight_stage::reload::reload_creatures;
+use crate::fight_stage::timeline::{
+    drain_health, move_vfx, play_timeline, HealthDisplay, Timeline,
+};
+use crate::fight_stage::turn::{continue_turn, replace_fainted, TurnStep};
+use crate::flags::FlagChange;
+use crate::loading::FontAssets;
+use crate::party::{PartyMember, PlayerParty};
 use crate::GameState;
 use bevy::prelude::*;
-use std::f32::consts::PI;
+use std::collections::VecDeque;
+
+mod action_bar;
+mod animation;
+mod reload;
+mod timeline;
+mod turn;
 
 pub struct MenuPlugin;
 
-/// This plugin is responsible for the game menu (containing only one button...)
-/// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
+/// This plugin is responsible for the fight stage and its buttons
+/// The fight is only drawn during the State `GameState::Fight` and is removed when that state is exited,
+/// which happens once one side has no creature left to fight or the player runs back to the overworld
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<ButtonColors>()
-            .init_resource::<Time>()
+        app.init_resource::<Time>()
             .add_system(setup_menu.in_schedule(OnEnter(GameState::Fight)))
-            .add_system(click_play_button.in_set(OnUpdate(GameState::Fight)))
+            .add_system(click_action_button.in_set(OnUpdate(GameState::Fight)))
+            .add_system(
+                continue_turn
+                    .after(click_action_button)
+                    .in_set(OnUpdate(GameState::Fight)),
+            )
+            .add_system(
+                replace_fainted
+                    .after(continue_turn)
+                    .in_set(OnUpdate(GameState::Fight)),
+            )
+            .add_system(
+                reload_creatures
+                    .after(replace_fainted)
+                    .in_set(OnUpdate(GameState::Fight)),
+            )
+            .add_system(
+                draw_action_bar
+                    .after(reload_creatures)
+                    .in_set(OnUpdate(GameState::Fight)),
+            )
+            .add_system(update_fight_log.in_set(OnUpdate(GameState::Fight)))
+            .add_system(describe_hovered_item.in_set(OnUpdate(GameState::Fight)))
             .add_system(update_health.in_set(OnUpdate(GameState::Fight)))
-            .add_system(wiggle_spriteses.in_set(OnUpdate(GameState::Fight)))
-            .add_system(update_health_bar.in_set(OnUpdate(GameState::Fight)));
+            .add_systems(
+                (
+                    play_timeline,
+                    use_sprite_sheets,
+                    animate_creatures,
+                    wiggle_spriteses,
+                )
+                    .chain()
+                    .after(replace_fainted)
+                    .in_set(OnUpdate(GameState::Fight)),
+            )
+            .add_systems((move_vfx, drain_health).in_set(OnUpdate(GameState::Fight)))
+            .add_system(update_health_bar.in_set(OnUpdate(GameState::Fight)))
+            .add_system(update_held_item_icon.in_set(OnUpdate(GameState::Fight)))
+            .add_system(cleanup_menu.in_schedule(OnExit(GameState::Fight)));
     }
 }
 
+/// The opponents of the next fight, set by whatever starts the fight
+#[derive(Resource, Clone)]
+pub struct PendingBattle {
+    /// Sent into the fight one after another
+    pub opponents: Vec<PartyMember>,
+    /// `None` for wild creatures, which the player can run away from
+    pub trainer: Option<Trainer>,
+    /// Applied to the story flags if the player wins
+    pub on_win: Vec<FlagChange>,
+}
+
+#[derive(Clone)]
+pub struct Trainer {
+    pub id: String,
+    pub name: String,
+    /// Lines said once the trainer lost
+    pub defeated: Vec<String>,
+}
+
+/// State of the running fight
 #[derive(Resource)]
-struct ButtonColors {
-    normal: Color,
-    hovered: Color,
+struct Fight {
+    trainer: Option<Trainer>,
+    on_win: Vec<FlagChange>,
+    /// The opponent currently fighting, as it entered the fight
+    opponent: PartyMember,
+    /// Opponents still waiting for their turn
+    opponents: VecDeque<PartyMember>,
+    /// Index in the [`PlayerParty`] of the creature currently fighting
+    player_index: usize,
+    /// What the buttons at the bottom show
+    menu: BarMenu,
+    /// The buttons need to be rebuilt, e.g. for a new creature or after using an item
+    redraw_bar: bool,
+    /// Coins paid out if the player wins, grows with every opponent that faints
+    prize: u32,
+    /// The rest of the current turn, each step waits for the [`Timeline`] to be idle
+    pending: VecDeque<TurnStep>,
+}
+
+/// What happened in the last few turns
+#[derive(Resource, Default)]
+struct FightLog {
+    lines: VecDeque<String>,
+}
+
+/// Lines of the [`FightLog`] shown at once
+const FIGHT_LOG_LINES: usize = 4;
+
+impl FightLog {
+    fn push(&mut self, line: String) {
+        info!("{line}");
+        self.lines.push_back(line);
+        while self.lines.len() > FIGHT_LOG_LINES {
+            self.lines.pop_front();
+        }
+    }
+}
+
+/// Which side of the fight a creature is on
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Player,
+    Opponent,
 }
 
-impl Default for ButtonColors {
-    fn default() -> Self {
-        ButtonColors {
-            normal: Color::rgb(0.15, 0.15, 0.15),
-            hovered: Color::rgb(0.25, 0.25, 0.25),
+impl Side {
+    /// Where the side's creature stands, its sounds come from there
+    fn position(self) -> Vec2 {
+        match self {
+            Side::Player => Vec2::new(-200.0, 50.0),
+            Side::Opponent => Vec2::new(200.0, 50.0),
+        }
+    }
+
+    fn other(self) -> Side {
+        match self {
+            Side::Player => Side::Opponent,
+            Side::Opponent => Side::Player,
         }
     }
 }
 
+/// Everything spawned for the fight carries this, so it can be removed when the fight is over
+#[derive(Component)]
+struct FightStage;
+
+#[derive(Component)]
+struct FightLogText;
+
 #[derive(Component)]
 struct HealthBar {}
 
+/// Shows the icon of the item the creature holds on its plaque
+#[derive(Component)]
+struct HeldItemIcon;
+
 fn create_creature_plaque(
     parent: &mut ChildBuilder,
     plaque_margins: UiRect,
     font: Handle<Font>,
     associated_creature: Creature,
+    side: Side,
 ) {
     let creature_tag = associated_creature.get_creature_tag();
+    let icon_tag = associated_creature.get_creature_tag();
+    let health_display = HealthDisplay::new(&associated_creature);
     parent
         .spawn(NodeBundle {
             style: Style {
@@ -78,6 +217,11 @@ fn create_creature_plaque(
                             style: creature_status_text_style.clone(),
                         },
                         // health
+                        TextSection {
+                            value: String::new(),
+                            style: creature_status_text_style.clone(),
+                        },
+                        // effects
                         TextSection {
                             value: String::new(),
                             style: creature_status_text_style,
@@ -88,7 +232,19 @@ fn create_creature_plaque(
                         ..default()
                     }),
                 )
-                .insert(associated_creature);
+                .insert((associated_creature, side, health_display));
+
+            parent
+                .spawn(ImageBundle {
+                    style: Style {
+                        size: Size::all(Val::Px(24.0)),
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                })
+                .insert((HeldItemIcon, icon_tag));
 
             parent
                 .spawn(NodeBundle {
@@ -117,21 +273,78 @@ fn create_creature_plaque(
         });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn setup_menu(
     mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
     font_assets: Res<FontAssets>,
-    texture_assets: Res<TextureAssets>,
-    button_colors: Res<ButtonColors>,
+    game_data: GameData,
+    asset_server: Res<AssetServer>,
+    pending_battle: Option<Res<PendingBattle>>,
+    mut party: ResMut<PlayerParty>,
+    mut dex: ResMut<Garbagedex>,
 ) {
-    commands.spawn(Camera2dBundle::default());
-    let mut button_position: UiRect = UiRect::horizontal(Val::Auto);
-    button_position.bottom = Val::Percent(20.0);
-    button_position.top = Val::Auto;
-
-    let creature = Creature::new("Jeff".to_string());
-    let opponent_creature = Creature::new("Bilbo".to_string());
+    let (Some(species_table), Some(items)) = (game_data.species(), game_data.items()) else {
+        panic!("The creature data is loaded in GameState::Loading");
+    };
+    let Some(pending_battle) = pending_battle else {
+        error!("Entered a fight without an opponent");
+        next_state.set(GameState::Playing);
+        return;
+    };
+    commands.remove_resource::<PendingBattle>();
+    if party.first_able().is_none() {
+        party.heal();
+    }
+    let mut opponents: VecDeque<PartyMember> = pending_battle.opponents.iter().cloned().collect();
+    let (Some(player_index), Some(opponent)) = (party.first_able(), opponents.pop_front()) else {
+        error!("One side of the fight has no creatures");
+        next_state.set(GameState::Playing);
+        return;
+    };
+    let member = &party.members[player_index];
+    let (Some(species), Some(opponent_species)) = (
+        species_table.get(&member.species),
+        species_table.get(&opponent.species),
+    ) else {
+        error!(
+            "Unknown species \"{}\" or \"{}\"",
+            member.species, opponent.species
+        );
+        next_state.set(GameState::Playing);
+        return;
+    };
+    let creature = member
+        .to_creature(species_table, items)
+        .expect("The species was looked up above");
+    let opponent_creature = opponent
+        .to_creature(species_table, items)
+        .expect("The species was looked up above");
     let creature_tag = creature.get_creature_tag();
     let opponent_creature_tag = opponent_creature.get_creature_tag();
+    let mut log = FightLog::default();
+    log.push(match &pending_battle.trainer {
+        Some(trainer) => format!(
+            "{} sends out {}!",
+            trainer.name,
+            opponent_creature.get_name()
+        ),
+        None => format!("A wild {} appeared!", opponent_creature.get_name()),
+    });
+    commands.insert_resource(log);
+    dex.see(&opponent.species);
+    commands.insert_resource(Fight {
+        trainer: pending_battle.trainer.clone(),
+        on_win: pending_battle.on_win.clone(),
+        opponent,
+        opponents,
+        player_index,
+        menu: BarMenu::Moves,
+        redraw_bar: true,
+        prize: 0,
+        pending: VecDeque::new(),
+    });
+    commands.insert_resource(Timeline::default());
 
     commands
         .spawn(NodeBundle {
@@ -142,6 +355,7 @@ fn setup_menu(
             },
             ..default()
         })
+        .insert(FightStage)
         .with_children(|parent| {
             // Play Area
             parent
@@ -153,6 +367,46 @@ fn setup_menu(
                     ..default()
                 })
                 .with_children(|parent| {
+                    parent
+                        .spawn(TextBundle::from_section(
+                            String::new(),
+                            TextStyle {
+                                font: font_assets.fira_sans.clone(),
+                                font_size: 22.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        ))
+                        .insert(FightLogText)
+                        .insert(Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                top: Val::Px(20.0),
+                                left: Val::Px(20.0),
+                                ..default()
+                            },
+                            ..default()
+                        });
+                    parent
+                        .spawn(TextBundle::from_section(
+                            String::new(),
+                            TextStyle {
+                                font: font_assets.fira_sans.clone(),
+                                font_size: 22.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        ))
+                        .insert(ItemDescription)
+                        // between the two plaques
+                        .insert(Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                bottom: Val::Px(20.0),
+                                left: Val::Px(240.0),
+                                right: Val::Px(240.0),
+                                ..default()
+                            },
+                            ..default()
+                        });
                     create_creature_plaque(
                         parent,
                         UiRect {
@@ -163,6 +417,7 @@ fn setup_menu(
                         },
                         font_assets.fira_sans.clone(),
                         creature,
+                        Side::Player,
                     );
                     create_creature_plaque(
                         parent,
@@ -174,10 +429,11 @@ fn setup_menu(
                         },
                         font_assets.fira_sans.clone(),
                         opponent_creature,
+                        Side::Opponent,
                     );
                 });
 
-            // Button area
+            // Button area, filled by `draw_action_bar`
             parent
                 .spawn(NodeBundle {
                     style: Style {
@@ -187,123 +443,101 @@ fn setup_menu(
                     },
                     ..default()
                 })
-                .with_children(|parent| {
-                    parent
-                        .spawn(ButtonBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(120.0), Val::Px(50.0)),
-                                margin: UiRect::all(Val::Auto),
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                ..default()
-                            },
-                            background_color: button_colors.normal.into(),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Attack",
-                                TextStyle {
-                                    font: font_assets.fira_sans.clone(),
-                                    font_size: 40.0,
-                                    color: Color::rgb(0.9, 0.9, 0.9),
-                                },
-                            ));
-                        });
-                });
+                .insert(ActionBar);
         });
 
     commands
-        .spawn(SpriteBundle {
-            texture: texture_assets.gargoylemon.clone(),
-            transform: Transform::from_translation(Vec3::new(-200.0, 50.0, 0.1))
+        .spawn(SpatialBundle::from_transform(
+            Transform::from_translation(Side::Player.position().extend(0.1))
                 .with_scale(Vec3::new(-1., 1., 1.)),
-            visibility: Visibility::Visible,
-            ..default()
-        })
-        .insert(creature_tag);
+        ))
+        .insert(creature_sprite(species, &asset_server))
+        .insert((creature_tag, Side::Player))
+        .insert(FightStage);
     commands
-        .spawn(SpriteBundle {
-            texture: texture_assets.gargoylemon.clone(),
-            transform: Transform::from_translation(Vec3::new(200.0, 50.0, 0.1)),
-            visibility: Visibility::Visible,
-            ..default()
-        })
-        .insert(opponent_creature_tag);
+        .spawn(SpatialBundle::from_transform(Transform::from_translation(
+            Side::Opponent.position().extend(0.1),
+        )))
+        .insert(creature_sprite(opponent_species, &asset_server))
+        .insert((opponent_creature_tag, Side::Opponent))
+        .insert(FightStage);
 }
 
-#[allow(clippy::type_complexity)]
-fn click_play_button(
-    button_colors: Res<ButtonColors>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-    mut creatures: Query<&mut Creature>,
-) {
-    for (interaction, mut color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                creatures.iter_mut().for_each(|mut creature| {
-                    let roll = rand::random::<u32>() % 7 + 1;
-                    creature.set_wiggle_period(f32::max(rand::random::<f32>(), 0.2) + 0.05);
-                    warn!("Dealt a _massive_ {roll} damage!");
-                    creature.take_damage(roll)
-                });
-            }
-            Interaction::Hovered => {
-                *color = button_colors.hovered.into();
-            }
-            Interaction::None => {
-                *color = button_colors.normal.into();
-            }
-        }
+fn cleanup_menu(mut commands: Commands, fight_stage: Query<Entity, With<FightStage>>) {
+    commands.remove_resource::<Fight>();
+    commands.remove_resource::<FightLog>();
+    commands.remove_resource::<Timeline>();
+    for entity in &fight_stage {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
-fn update_health(mut health_text_query: Query<(&mut Text, &Creature), With<Creature>>) {
-    for (mut text, creature) in health_text_query.iter_mut() {
-        text.sections[0].value = format!("Name: {}\n", creature.get_name());
-        text.sections[1].value = format!("Health: {}", creature.get_health());
+fn update_fight_log(log: Res<FightLog>, mut text_query: Query<&mut Text, With<FightLogText>>) {
+    if !log.is_changed() {
+        return;
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = log.lines.iter().cloned().collect::<Vec<_>>().join("\n");
+    }
+}
+
+fn update_health(mut health_text_query: Query<(&mut Text, &Creature, &HealthDisplay)>) {
+    for (mut text, creature, health_display) in health_text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "Name: {} (Lv. {})\n",
+            creature.get_name(),
+            creature.get_level()
+        );
+        text.sections[1].value = format!("Health: {}", health_display.shown.round());
+        text.sections[2].value = creature
+            .get_effects()
+            .iter()
+            .map(|active| format!("\n{}", active.effect.label()))
+            .collect();
     }
 }
 
 fn update_health_bar(
     mut healthbar_query: Query<(&mut Style, &HealthBar, &CreatureTag)>,
-    creature_query: Query<&Creature>,
+    creature_query: Query<(&Creature, &HealthDisplay)>,
 ) {
     for (mut style, _, creature_tag) in healthbar_query.iter_mut() {
-        let creature = creature_query
+        let (creature, health_display) = creature_query
             .iter()
-            .find(|creature| &creature.get_creature_tag() == creature_tag)
+            .find(|(creature, _)| &creature.get_creature_tag() == creature_tag)
             .expect("Healthbar doesn't have associated creature!");
-        let health_percentage =
-            (creature.get_health() as f32 / creature.get_max_health() as f32) * 100.0;
+        let max_health = creature.get_max_health() as f32;
+        let health_percentage = (health_display.shown.min(max_health) / max_health) * 100.0;
         style.size.width = Val::Percent(health_percentage);
     }
 }
 
-/// My precious
-fn wiggle_spriteses(
-    time: Res<Time>,
-    mut sprite_query: Query<(&mut Transform, &CreatureTag), Without<HealthBar>>,
-    creature_query: Query<&Creature>,
+fn update_held_item_icon(
+    asset_server: Res<AssetServer>,
+    mut icon_query: Query<(&mut UiImage, &mut Visibility, &CreatureTag), With<HeldItemIcon>>,
+    creature_query: Query<&Creature, Changed<Creature>>,
 ) {
-    for (mut transform, tag) in sprite_query.iter_mut() {
-        let creature = creature_query
-            .iter()
-            .find(|creature| &creature.get_creature_tag() == tag)
-            .expect("Sprite doesn't have an associated creature!");
-
-        let rotation = ((time.elapsed_seconds() / creature.get_wiggle_period()) * (2. * PI)).cos()
-            * WIGGLE_MAX_ANGLE;
-
-        info!(
-            "Creature called \"{}\"'s rotation is {}!",
-            creature.get_name(),
-            rotation
-        );
-
-        transform.rotation = Quat::from_rotation_z(rotation);
+    for creature in &creature_query {
+        let creature_tag = creature.get_creature_tag();
+        let icon = creature
+            .get_held_item()
+            .and_then(|held_item| held_item.icon.as_ref());
+        for (mut image, mut visibility, icon_tag) in &mut icon_query {
+            if *icon_tag != creature_tag {
+                continue;
+            }
+            match icon {
+                Some(icon) => {
+                    let texture = asset_server.load(icon.as_str());
+                    if image.texture != texture {
+                        image.texture = texture;
+                    }
+                    visibility.set_if_neq(Visibility::Inherited);
+                }
+                None => {
+                    visibility.set_if_neq(Visibility::Hidden);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,173 @@
+use crate::actions::Actions;
+use crate::creature::Creature;
+use crate::data::GameData;
+use crate::fight_stage::turn::{take_turn, PlayerAction, TurnContext};
+use crate::fight_stage::{Fight, Side};
+use crate::inventory::Inventory;
+use crate::loading::FontAssets;
+use crate::ui::focus::{CancelButton, Focus};
+use crate::ui::{spawn_button, ButtonColors};
+use crate::GameState;
+use bevy::prelude::*;
+
+const BUTTON_SIZE: Size = Size::new(Val::Px(160.0), Val::Px(50.0));
+
+/// Holds the buttons for whatever the player can do next
+#[derive(Component)]
+pub(super) struct ActionBar;
+
+/// Explains the item button under the mouse or the focus, above the [`ActionBar`]
+#[derive(Component)]
+pub(super) struct ItemDescription;
+
+/// Which buttons the [`ActionBar`] shows
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum BarMenu {
+    Moves,
+    Items,
+}
+
+#[derive(Component)]
+pub(super) enum ActionButton {
+    /// Uses the move with this index in [`Creature::get_moves`]
+    Move(usize),
+    /// Opens the item menu
+    Items,
+    /// Uses the item with this id
+    Item(String),
+    /// Back from the item menu to the moves
+    Back,
+    Run,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn draw_action_bar(
+    mut commands: Commands,
+    mut fight: ResMut<Fight>,
+    inventory: Res<Inventory>,
+    game_data: GameData,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    creatures: Query<(&Creature, &Side)>,
+    action_bar: Query<Entity, With<ActionBar>>,
+) {
+    if !fight.redraw_bar {
+        return;
+    }
+    let (Some(move_table), Some(item_table)) = (game_data.moves(), game_data.items()) else {
+        return;
+    };
+    fight.redraw_bar = false;
+    let moves: Vec<String> = creatures
+        .iter()
+        .find(|(_, side)| **side == Side::Player)
+        .map(|(creature, _)| creature.get_moves().to_vec())
+        .unwrap_or_default();
+    let button = |parent: &mut ChildBuilder, label: &str, action: ActionButton| {
+        let is_cancel = matches!(action, ActionButton::Back | ActionButton::Run);
+        let mut button = spawn_button(
+            parent,
+            label,
+            font_assets.fira_sans.clone(),
+            &button_colors,
+            BUTTON_SIZE,
+        );
+        button.insert(action);
+        if is_cancel {
+            button.insert(CancelButton);
+        }
+    };
+    for entity in &action_bar {
+        let mut action_bar = commands.entity(entity);
+        action_bar.despawn_descendants();
+        action_bar.with_children(|parent| match fight.menu {
+            BarMenu::Moves => {
+                for (index, id) in moves.iter().enumerate() {
+                    let label = move_table
+                        .get(id)
+                        .map_or(id.as_str(), |known_move| known_move.name.as_str());
+                    button(parent, label, ActionButton::Move(index));
+                }
+                button(parent, "Items", ActionButton::Items);
+                // there is no running from trainers
+                if fight.trainer.is_none() {
+                    button(parent, "Run", ActionButton::Run);
+                }
+            }
+            BarMenu::Items => {
+                for (id, count) in &inventory.items {
+                    let name = item_table
+                        .get(id)
+                        .map_or(id.as_str(), |item| item.name.as_str());
+                    button(
+                        parent,
+                        &format!("{name} x{count}"),
+                        ActionButton::Item(id.clone()),
+                    );
+                }
+                button(parent, "Back", ActionButton::Back);
+            }
+        });
+    }
+}
+
+pub(super) fn click_action_button(
+    actions: Res<Actions>,
+    mut turn: TurnContext,
+    interaction_query: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+) {
+    // the buttons wait for the moves to play out
+    if turn.is_busy() {
+        return;
+    }
+    let clicked = interaction_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| button);
+    let action = match (clicked, actions.selected_move) {
+        (Some(ActionButton::Move(index)), _) => PlayerAction::Move(*index),
+        (Some(ActionButton::Item(item)), _) => PlayerAction::Item(item.clone()),
+        (Some(ActionButton::Items), _) => {
+            turn.fight.menu = BarMenu::Items;
+            turn.fight.redraw_bar = true;
+            return;
+        }
+        (Some(ActionButton::Back), _) => {
+            turn.fight.menu = BarMenu::Moves;
+            turn.fight.redraw_bar = true;
+            return;
+        }
+        (Some(ActionButton::Run), _) => {
+            turn.next_state.set(GameState::Playing);
+            return;
+        }
+        (None, Some(index)) if turn.fight.menu == BarMenu::Moves => PlayerAction::Move(index),
+        (None, _) => return,
+    };
+    take_turn(&mut turn, action);
+}
+
+pub(super) fn describe_hovered_item(
+    focus: Res<Focus>,
+    game_data: GameData,
+    buttons: Query<(Entity, &Interaction, &ActionButton)>,
+    mut text_query: Query<&mut Text, With<ItemDescription>>,
+) {
+    let description = buttons
+        .iter()
+        .find_map(|(entity, interaction, button)| match button {
+            ActionButton::Item(id)
+                if *interaction == Interaction::Hovered || focus.entity == Some(entity) =>
+            {
+                Some(id)
+            }
+            _ => None,
+        })
+        .and_then(|id| game_data.items().and_then(|items| items.get(id)))
+        .map_or("", |item| item.description.as_str());
+    for mut text in &mut text_query {
+        if text.sections[0].value != description {
+            text.sections[0].value = description.to_string();
+        }
+    }
+}
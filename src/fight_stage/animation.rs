@@ -0,0 +1,291 @@
+use crate::creature::animations::{AnimationState, CreatureAnimations};
+use crate::creature::species::Species;
+use crate::creature::{Creature, CreatureTag, WIGGLE_MAX_ANGLE};
+use crate::settings::Settings;
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+/// Plays the clips of a creature's [`CreatureAnimations`] on its battle sprite
+///
+/// The fight switches the state with [`Animator::play`]. Once a clip that doesn't loop is over,
+/// the creature goes back to idling or holds the last frame, see [`AnimationState::next`].
+#[derive(Component)]
+pub(super) struct Animator {
+    /// `None` for species without a sprite sheet
+    animations: Option<Handle<CreatureAnimations>>,
+    state: AnimationState,
+    /// Position in the frames of the current clip
+    frame: usize,
+    /// Seconds the current frame has been shown
+    elapsed: f32,
+    finished: bool,
+}
+
+impl Animator {
+    pub(super) fn new(animations: Option<Handle<CreatureAnimations>>) -> Self {
+        Animator {
+            animations,
+            state: AnimationState::Idle,
+            frame: 0,
+            elapsed: 0.,
+            finished: false,
+        }
+    }
+
+    pub(super) fn play(&mut self, state: AnimationState) {
+        self.state = state;
+        self.frame = 0;
+        self.elapsed = 0.;
+        self.finished = false;
+    }
+
+    pub(super) fn state(&self) -> AnimationState {
+        self.state
+    }
+
+    /// Whether a clip that doesn't loop got to its end, clips missing from the sheet end right away
+    pub(super) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn finish(&mut self) {
+        match self.state.next() {
+            Some(next) => self.play(next),
+            None => self.finished = true,
+        }
+    }
+
+    /// Moves the current clip along by `delta` seconds
+    fn advance(&mut self, animations: Option<&CreatureAnimations>, delta: f32) {
+        if self.finished {
+            return;
+        }
+        let Some(clip) = animations.and_then(|animations| animations.clip(self.state)) else {
+            self.finish();
+            return;
+        };
+        self.elapsed += delta;
+        if self.elapsed < 1. / clip.fps {
+            return;
+        }
+        self.elapsed = 0.;
+        if self.frame + 1 < clip.frames.len() {
+            self.frame += 1;
+        } else if clip.looping {
+            self.frame = 0;
+        } else {
+            self.finish();
+        }
+    }
+
+    /// The frame of the sheet to show, `None` keeps the one shown before
+    fn sheet_index(&self, animations: &CreatureAnimations) -> Option<usize> {
+        animations
+            .clip(self.state)
+            .and_then(|clip| clip.frames.get(self.frame))
+            .copied()
+    }
+
+    /// Creatures without a sprite sheet wiggle while idle
+    fn wiggles(&self, animations: Option<&CreatureAnimations>) -> bool {
+        match animations {
+            Some(animations) => animations.clip(self.state).is_some_and(|clip| clip.wiggle),
+            None => self.state == AnimationState::Idle,
+        }
+    }
+}
+
+/// The battle sprite of a species
+///
+/// It starts out as the plain sprite and is switched to the sprite sheet by
+/// [`use_sprite_sheets`] once the animations are loaded.
+pub(super) fn creature_sprite(
+    species: &Species,
+    asset_server: &AssetServer,
+) -> (Sprite, Handle<Image>, Animator) {
+    let animations = species
+        .animations
+        .as_ref()
+        .map(|path| asset_server.load(path.as_str()));
+    (
+        Sprite::default(),
+        asset_server.load(species.sprite.as_str()),
+        Animator::new(animations),
+    )
+}
+
+/// Swaps the plain sprite for the sprite sheet once it is loaded, and again when the
+/// animations are changed on disk
+pub(super) fn use_sprite_sheets(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<CreatureAnimations>>,
+    asset_server: Res<AssetServer>,
+    animations: Res<Assets<CreatureAnimations>>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+    sprites: Query<(Entity, &Animator, Option<&Handle<TextureAtlas>>)>,
+) {
+    let modified: Vec<Handle<CreatureAnimations>> = events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => Some(handle.clone()),
+            AssetEvent::Created { .. } | AssetEvent::Removed { .. } => None,
+        })
+        .collect();
+    for (entity, animator, atlas) in &sprites {
+        let Some(handle) = &animator.animations else {
+            continue;
+        };
+        if atlas.is_some() && !modified.contains(handle) {
+            continue;
+        }
+        let Some(loaded) = animations.get(handle) else {
+            continue;
+        };
+        let atlas = TextureAtlas::from_grid(
+            asset_server.load(loaded.sheet.as_str()),
+            Vec2::new(loaded.frame_size.0, loaded.frame_size.1),
+            loaded.columns,
+            loaded.rows,
+            None,
+            None,
+        );
+        commands
+            .entity(entity)
+            .remove::<(Sprite, Handle<Image>)>()
+            .insert((
+                TextureAtlasSprite::new(animator.sheet_index(loaded).unwrap_or_default()),
+                atlases.add(atlas),
+            ));
+    }
+}
+
+pub(super) fn animate_creatures(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    animations: Res<Assets<CreatureAnimations>>,
+    mut sprites: Query<(&mut Animator, Option<&mut TextureAtlasSprite>)>,
+) {
+    let delta = time.delta_seconds() * settings.gameplay.battle_animation_speed;
+    for (mut animator, sprite) in &mut sprites {
+        let loaded = animator
+            .animations
+            .as_ref()
+            .and_then(|handle| animations.get(handle));
+        animator.advance(loaded, delta);
+        let (Some(mut sprite), Some(loaded)) = (sprite, loaded) else {
+            continue;
+        };
+        if let Some(index) = animator
+            .sheet_index(loaded)
+            .filter(|index| *index != sprite.index)
+        {
+            sprite.index = index;
+        }
+    }
+}
+
+/// My precious
+pub(super) fn wiggle_spriteses(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    animations: Res<Assets<CreatureAnimations>>,
+    mut sprite_query: Query<(&mut Transform, &CreatureTag, &Animator)>,
+    creature_query: Query<&Creature>,
+) {
+    for (mut transform, tag, animator) in sprite_query.iter_mut() {
+        let loaded = animator
+            .animations
+            .as_ref()
+            .and_then(|handle| animations.get(handle));
+        if !animator.wiggles(loaded) {
+            transform.rotation = Quat::IDENTITY;
+            continue;
+        }
+        let creature = creature_query
+            .iter()
+            .find(|creature| &creature.get_creature_tag() == tag)
+            .expect("Sprite doesn't have an associated creature!");
+
+        let elapsed = time.elapsed_seconds() * settings.gameplay.battle_animation_speed;
+        let rotation =
+            ((elapsed / creature.get_wiggle_period()) * (2. * PI)).cos() * WIGGLE_MAX_ANGLE;
+
+        transform.rotation = Quat::from_rotation_z(rotation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::creature::animations::Clip;
+
+    fn animations() -> CreatureAnimations {
+        let clip = |frames: Vec<usize>, looping| Clip {
+            frames,
+            fps: 10.,
+            looping,
+            wiggle: false,
+        };
+        CreatureAnimations {
+            sheet: String::new(),
+            frame_size: (10., 10.),
+            columns: 4,
+            rows: 1,
+            clips: [
+                (AnimationState::Idle, clip(vec![0], true)),
+                (AnimationState::Attack, clip(vec![1, 2], false)),
+                (AnimationState::Faint, clip(vec![2, 3], false)),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    fn frames(
+        animator: &mut Animator,
+        animations: &CreatureAnimations,
+        steps: usize,
+    ) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                animator.advance(Some(animations), 0.1);
+                animator.sheet_index(animations).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn attacks_go_back_to_idle() {
+        let animations = animations();
+        let mut animator = Animator::new(None);
+        animator.play(AnimationState::Attack);
+        assert_eq!(animator.sheet_index(&animations), Some(1));
+        assert_eq!(frames(&mut animator, &animations, 3), [2, 0, 0]);
+        assert_eq!(animator.state(), AnimationState::Idle);
+        assert!(!animator.is_finished());
+    }
+
+    #[test]
+    fn fainting_holds_the_last_frame() {
+        let animations = animations();
+        let mut animator = Animator::new(None);
+        animator.play(AnimationState::Faint);
+        assert_eq!(frames(&mut animator, &animations, 3), [3, 3, 3]);
+        assert!(animator.is_finished());
+    }
+
+    #[test]
+    fn missing_clips_end_right_away() {
+        let animations = animations();
+        let mut animator = Animator::new(None);
+        animator.play(AnimationState::Hurt);
+        animator.advance(Some(&animations), 0.01);
+        assert_eq!(animator.state(), AnimationState::Idle);
+
+        // without a sprite sheet there is nothing to wait for
+        animator.play(AnimationState::Victory);
+        animator.advance(None, 0.01);
+        assert!(animator.is_finished());
+        assert!(!animator.wiggles(None));
+    }
+}
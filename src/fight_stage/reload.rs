@@ -0,0 +1,89 @@
+use crate::creature::moves::MoveTable;
+use crate::creature::species::SpeciesTable;
+use crate::creature::Creature;
+use crate::data::GameData;
+use crate::fight_stage::{Fight, Side};
+use crate::inventory::items::ItemTable;
+use crate::party::{PartyMember, PlayerParty};
+use bevy::prelude::*;
+
+/// Rebuilds the fighting creatures once species, moves or items were changed on disk
+///
+/// A creature whose data doesn't add up anymore, like a move that doesn't exist, keeps the
+/// version it entered the fight with.
+pub(super) fn reload_creatures(
+    mut fight: ResMut<Fight>,
+    game_data: GameData,
+    party: Res<PlayerParty>,
+    mut species_events: EventReader<AssetEvent<SpeciesTable>>,
+    mut move_events: EventReader<AssetEvent<MoveTable>>,
+    mut item_events: EventReader<AssetEvent<ItemTable>>,
+    mut creatures: Query<(&mut Creature, &Side)>,
+) {
+    let modified = species_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }))
+        | move_events
+            .iter()
+            .any(|event| matches!(event, AssetEvent::Modified { .. }))
+        | item_events
+            .iter()
+            .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    if !modified {
+        return;
+    }
+    let (Some(species_table), Some(move_table), Some(items)) =
+        (game_data.species(), game_data.moves(), game_data.items())
+    else {
+        return;
+    };
+    for (mut creature, side) in &mut creatures {
+        let member = match side {
+            Side::Player => party.members.get(fight.player_index),
+            Side::Opponent => Some(&fight.opponent),
+        };
+        let Some(member) = member else {
+            continue;
+        };
+        match rebuild(member, species_table, move_table, items) {
+            Ok(fresh) => {
+                info!("Reloaded {} from the changed data", fresh.get_name());
+                creature.reload(fresh);
+            }
+            Err(problem) => error!(
+                "Rejected the reloaded data for {}: {problem}",
+                creature.get_name()
+            ),
+        }
+    }
+    fight.redraw_bar = true;
+}
+
+fn rebuild(
+    member: &PartyMember,
+    species_table: &SpeciesTable,
+    move_table: &MoveTable,
+    items: &ItemTable,
+) -> Result<Creature, String> {
+    if let Some(item) = member
+        .held_item
+        .as_ref()
+        .filter(|item| items.get(item).is_none())
+    {
+        return Err(format!("the held item \"{item}\" doesn't exist"));
+    }
+    let fresh = member
+        .to_creature(species_table, items)
+        .ok_or_else(|| format!("the species \"{}\" doesn't exist", member.species))?;
+    if let Some(unknown) = fresh
+        .get_moves()
+        .iter()
+        .find(|move_id| move_table.get(move_id).is_none())
+    {
+        return Err(format!("the move \"{unknown}\" doesn't exist"));
+    }
+    if fresh.get_moves().is_empty() {
+        return Err("it doesn't know any moves".to_string());
+    }
+    Ok(fresh)
+}
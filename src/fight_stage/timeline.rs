@@ -0,0 +1,549 @@
+use crate::audio::sfx::{PlaySfx, Sfx};
+use crate::creature::animations::AnimationState;
+use crate::creature::moves::{Cue, Roll};
+use crate::creature::Creature;
+use crate::fight_stage::animation::Animator;
+use crate::fight_stage::{FightStage, Side};
+use crate::loading::FontAssets;
+use crate::settings::Settings;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+/// Share of the max health a health bar drains per second
+const HEALTH_DRAIN_SPEED: f32 = 1.5;
+/// Seconds a damage number floats above the target
+const DAMAGE_NUMBER_SECONDS: f32 = 1.0;
+
+/// The cues of one move, played against its target
+pub(super) struct Sequence {
+    attacker: Side,
+    target: Side,
+    cues: VecDeque<Cue>,
+    roll: Roll,
+    /// The [`Cue::Damage`] was reached, so the target's health bar may drain
+    damage_shown: bool,
+    hurt: bool,
+}
+
+impl Sequence {
+    pub(super) fn new(attacker: Side, cues: Vec<Cue>, roll: Roll) -> Self {
+        Sequence {
+            attacker,
+            target: attacker.other(),
+            cues: cues.into(),
+            roll,
+            damage_shown: false,
+            hurt: false,
+        }
+    }
+}
+
+/// Plays the [`Sequence`]s of the used moves one cue after another
+///
+/// The turn only goes on once the timeline is idle, see [`turn::continue_turn`](super::turn::continue_turn).
+#[derive(Resource, Default)]
+pub(super) struct Timeline {
+    queue: VecDeque<Sequence>,
+    /// Seconds the current cue has been playing, `None` if it didn't start yet
+    elapsed: Option<f32>,
+}
+
+impl Timeline {
+    pub(super) fn play(&mut self, sequence: Sequence) {
+        self.queue.push_back(sequence);
+    }
+
+    pub(super) fn is_idle(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Health bars of a side wait for the damage of the moves aimed at it to pop up
+    fn holds_health(&self, side: Side) -> bool {
+        self.queue
+            .iter()
+            .any(|sequence| sequence.target == side && !sequence.damage_shown)
+    }
+}
+
+/// The health shown on a creature's plaque, which drains towards its actual health
+#[derive(Component)]
+pub(super) struct HealthDisplay {
+    pub(super) shown: f32,
+}
+
+impl HealthDisplay {
+    pub(super) fn new(creature: &Creature) -> Self {
+        HealthDisplay {
+            shown: creature.get_health() as f32,
+        }
+    }
+}
+
+/// A short lived sprite or text, like a projectile, a particle or a damage number
+#[derive(Component)]
+pub(super) struct Vfx {
+    velocity: Vec2,
+    remaining: f32,
+    /// Turns transparent over its lifetime
+    fade: Option<f32>,
+}
+
+/// Where the creature sprites are pushed by lunges and recoils, and how they are tinted
+#[derive(Default)]
+struct Pose {
+    offset: Vec2,
+    tint: Option<Color>,
+}
+
+type CreatureSprites<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Side,
+        &'static mut Transform,
+        &'static mut Animator,
+        Option<&'static mut Sprite>,
+        Option<&'static mut TextureAtlasSprite>,
+    ),
+>;
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn play_timeline(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    font_assets: Res<FontAssets>,
+    asset_server: Res<AssetServer>,
+    mut timeline: ResMut<Timeline>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut sprites: CreatureSprites,
+) {
+    let delta = time.delta_seconds() * settings.gameplay.battle_animation_speed;
+    let mut poses = [Pose::default(), Pose::default()];
+    let pose_index = |side: Side| match side {
+        Side::Player => 0,
+        Side::Opponent => 1,
+    };
+    loop {
+        let Timeline { queue, elapsed } = &mut *timeline;
+        let Some(sequence) = queue.front_mut() else {
+            break;
+        };
+        let Some(cue) = sequence.cues.front().cloned() else {
+            queue.pop_front();
+            continue;
+        };
+        let missed = sequence.roll.damage == 0;
+        if missed && cue.is_impact() {
+            sequence.cues.pop_front();
+            continue;
+        }
+        let (attacker, target) = (sequence.attacker.position(), sequence.target.position());
+        let started = elapsed.is_some();
+        let seconds = elapsed.get_or_insert(0.);
+        if !started {
+            if !sequence.hurt && !missed && (cue.is_impact() || matches!(cue, Cue::Damage { .. })) {
+                sequence.hurt = true;
+                animate(&mut sprites, sequence.target, AnimationState::Hurt);
+            }
+            if let Cue::Damage { .. } = cue {
+                let hit = match sequence.roll {
+                    Roll { damage: 0, .. } => Sfx::Miss,
+                    Roll { critical: true, .. } => Sfx::Critical,
+                    Roll { .. } => Sfx::Hit,
+                };
+                sfx.send(PlaySfx::new(hit).at(sequence.target.position()));
+            }
+            start_cue(
+                &mut commands,
+                &cue,
+                sequence,
+                &font_assets,
+                &asset_server,
+                &mut sprites,
+            );
+        } else {
+            *seconds += delta;
+        }
+        let progress = if cue.seconds() > 0. {
+            (*seconds / cue.seconds()).min(1.)
+        } else {
+            1.
+        };
+        // lunges and recoils go out and back
+        let swing = (progress * PI).sin();
+        let towards_target = (target - attacker).normalize_or_zero();
+        match cue {
+            Cue::Lunge { distance, .. } => {
+                poses[pose_index(sequence.attacker)].offset = towards_target * distance * swing;
+            }
+            Cue::Recoil { distance, .. } => {
+                poses[pose_index(sequence.target)].offset = towards_target * distance * swing;
+            }
+            Cue::Flash { color, .. } => poses[pose_index(sequence.target)].tint = Some(color),
+            Cue::Projectile { .. } | Cue::Particles { .. } | Cue::Damage { .. } | Cue::Wait(_) => {}
+        }
+        if progress < 1. {
+            break;
+        }
+        sequence.cues.pop_front();
+        *elapsed = None;
+        // the cue is over, the next one starts right away
+        poses = [Pose::default(), Pose::default()];
+    }
+
+    for (side, mut transform, _, sprite, atlas_sprite) in &mut sprites {
+        let pose = &poses[pose_index(*side)];
+        let translation = (side.position() + pose.offset).extend(transform.translation.z);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+        let color = pose.tint.unwrap_or(Color::WHITE);
+        if let Some(mut sprite) = sprite.filter(|sprite| sprite.color != color) {
+            sprite.color = color;
+        }
+        if let Some(mut sprite) = atlas_sprite.filter(|sprite| sprite.color != color) {
+            sprite.color = color;
+        }
+    }
+}
+
+fn animate(sprites: &mut CreatureSprites, side: Side, state: AnimationState) {
+    for (sprite_side, _, mut animator, _, _) in sprites.iter_mut() {
+        if *sprite_side == side {
+            animator.play(state);
+        }
+    }
+}
+
+/// Spawns whatever the cue shows
+fn start_cue(
+    commands: &mut Commands,
+    cue: &Cue,
+    sequence: &mut Sequence,
+    font_assets: &FontAssets,
+    asset_server: &AssetServer,
+    sprites: &mut CreatureSprites,
+) {
+    let attacker = sequence.attacker.position();
+    let target = sequence.target.position();
+    match cue {
+        Cue::Lunge { .. } => animate(sprites, sequence.attacker, AnimationState::Attack),
+        Cue::Projectile {
+            sprite,
+            color,
+            size,
+            seconds,
+        } => {
+            animate(sprites, sequence.attacker, AnimationState::Attack);
+            let mut bundle = SpriteBundle {
+                sprite: Sprite {
+                    color: *color,
+                    custom_size: Some(Vec2::splat(*size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(attacker.extend(0.5)),
+                ..default()
+            };
+            if let Some(sprite) = sprite {
+                bundle.texture = asset_server.load(sprite.as_str());
+            }
+            commands.spawn(bundle).insert((
+                Vfx {
+                    velocity: (target - attacker) / seconds.max(0.01),
+                    remaining: *seconds,
+                    fade: None,
+                },
+                FightStage,
+            ));
+        }
+        Cue::Particles {
+            color,
+            count,
+            seconds,
+        } => {
+            for _ in 0..*count {
+                let angle = rand::random::<f32>() * 2. * PI;
+                let speed = 80. + rand::random::<f32>() * 120.;
+                commands
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: *color,
+                            custom_size: Some(Vec2::splat(6.)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(target.extend(0.5)),
+                        ..default()
+                    })
+                    .insert((
+                        Vfx {
+                            velocity: Vec2::from_angle(angle) * speed,
+                            remaining: *seconds,
+                            fade: Some(*seconds),
+                        },
+                        FightStage,
+                    ));
+            }
+        }
+        Cue::Damage { .. } => {
+            sequence.damage_shown = true;
+            let (text, color, size) = match sequence.roll {
+                Roll { damage: 0, .. } => ("Miss".to_string(), Color::rgb(0.8, 0.8, 0.8), 30.),
+                Roll {
+                    damage,
+                    critical: true,
+                } => (format!("{damage}!"), Color::rgb(1., 0.85, 0.2), 44.),
+                Roll { damage, .. } => (damage.to_string(), Color::WHITE, 36.),
+            };
+            commands
+                .spawn(Text2dBundle {
+                    text: Text::from_section(
+                        text,
+                        TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: size,
+                            color,
+                        },
+                    ),
+                    transform: Transform::from_translation((target + Vec2::Y * 90.).extend(0.6)),
+                    ..default()
+                })
+                .insert((
+                    Vfx {
+                        velocity: Vec2::Y * 40.,
+                        remaining: DAMAGE_NUMBER_SECONDS,
+                        fade: Some(DAMAGE_NUMBER_SECONDS),
+                    },
+                    FightStage,
+                ));
+        }
+        Cue::Flash { .. } | Cue::Recoil { .. } | Cue::Wait(_) => {}
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(super) fn move_vfx(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut vfx_query: Query<(
+        Entity,
+        &mut Vfx,
+        &mut Transform,
+        Option<&mut Sprite>,
+        Option<&mut Text>,
+    )>,
+) {
+    let delta = time.delta_seconds() * settings.gameplay.battle_animation_speed;
+    for (entity, mut vfx, mut transform, sprite, text) in &mut vfx_query {
+        vfx.remaining -= delta;
+        if vfx.remaining <= 0. {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation += (vfx.velocity * delta).extend(0.);
+        let Some(fade) = vfx.fade else {
+            continue;
+        };
+        let alpha = vfx.remaining / fade;
+        if let Some(mut sprite) = sprite {
+            sprite.color.set_a(alpha);
+        }
+        if let Some(mut text) = text {
+            for section in &mut text.sections {
+                section.style.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+/// Moves the shown health towards the actual health, once the timeline lets it
+pub(super) fn drain_health(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    timeline: Res<Timeline>,
+    mut creatures: Query<(&Creature, &Side, &mut HealthDisplay)>,
+) {
+    let delta = time.delta_seconds() * settings.gameplay.battle_animation_speed;
+    for (creature, side, mut display) in &mut creatures {
+        // a reload may have lowered the max health, the bar can't be more than full
+        let max_health = creature.get_max_health() as f32;
+        if display.shown > max_health {
+            display.shown = max_health;
+        }
+        let health = creature.get_health() as f32;
+        if display.shown == health || timeline.holds_health(*side) {
+            continue;
+        }
+        let step = max_health * HEALTH_DRAIN_SPEED * delta;
+        display.shown = if display.shown > health {
+            (display.shown - step).max(health)
+        } else {
+            (display.shown + step).min(health)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::creature::species::Species;
+    use bevy::asset::FileAssetIo;
+    use bevy::utils::{Duration, Instant};
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    /// A fight without anything on screen but the creatures
+    fn fight(sequence: Sequence) -> (World, Schedule) {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        world.insert_resource(Settings::default());
+        world.insert_resource(FontAssets {
+            fira_sans: Handle::default(),
+        });
+        world.insert_resource(AssetServer::new(FileAssetIo::new("assets", false)));
+        world.init_resource::<Events<PlaySfx>>();
+        let mut timeline = Timeline::default();
+        timeline.play(sequence);
+        world.insert_resource(timeline);
+        for side in [Side::Player, Side::Opponent] {
+            world.spawn((side, Transform::default(), Animator::new(None)));
+        }
+        let mut schedule = Schedule::new();
+        schedule.add_systems((play_timeline, drain_health).chain());
+        (world, schedule)
+    }
+
+    /// Runs the schedule with [`STEP`] seconds passing
+    fn step(world: &mut World, schedule: &mut Schedule) {
+        let mut time = world.resource_mut::<Time>();
+        let now = time.last_update().unwrap_or_else(Instant::now) + STEP;
+        time.update_with_instant(now);
+        schedule.run(world);
+    }
+
+    fn opponent(health: u32) -> (Creature, Side, HealthDisplay) {
+        let species = Species {
+            name: "Binrat".to_string(),
+            description: String::new(),
+            elements: vec![],
+            sprite: String::new(),
+            animations: None,
+            base_health: 40,
+            health_per_level: 0,
+            moves: vec![],
+        };
+        let creature = Creature::new(&species, 1);
+        let display = HealthDisplay::new(&creature);
+        (creature.with_health(health), Side::Opponent, display)
+    }
+
+    fn shown(world: &mut World) -> f32 {
+        world.query::<&HealthDisplay>().single(world).shown
+    }
+
+    fn impacts() -> Vec<Cue> {
+        vec![
+            Cue::Lunge {
+                distance: 10.,
+                seconds: 0.,
+            },
+            Cue::Particles {
+                color: Color::WHITE,
+                count: 5,
+                seconds: 0.,
+            },
+            Cue::Flash {
+                color: Color::RED,
+                seconds: 0.,
+            },
+            Cue::Damage { seconds: 0. },
+        ]
+    }
+
+    fn played(world: &mut World) -> (usize, Vec<Sfx>, AnimationState) {
+        let vfx = world.query::<&Vfx>().iter(world).count();
+        let events = world.resource::<Events<PlaySfx>>();
+        let sfx = events
+            .get_reader()
+            .iter(events)
+            .map(|event| event.sfx)
+            .collect();
+        let hurt = world
+            .query::<(&Side, &Animator)>()
+            .iter(world)
+            .find(|(side, _)| **side == Side::Opponent)
+            .map(|(_, animator)| animator.state())
+            .unwrap();
+        (vfx, sfx, hurt)
+    }
+
+    #[test]
+    fn misses_skip_the_impact() {
+        let hit = Roll {
+            damage: 4,
+            critical: false,
+        };
+        let (mut world, mut schedule) = fight(Sequence::new(Side::Player, impacts(), hit));
+        step(&mut world, &mut schedule);
+        assert!(world.resource::<Timeline>().is_idle());
+        // the particles and the damage number
+        assert_eq!(
+            played(&mut world),
+            (6, vec![Sfx::Hit], AnimationState::Hurt)
+        );
+
+        let miss = Roll {
+            damage: 0,
+            critical: false,
+        };
+        let (mut world, mut schedule) = fight(Sequence::new(Side::Player, impacts(), miss));
+        step(&mut world, &mut schedule);
+        assert!(world.resource::<Timeline>().is_idle());
+        assert_eq!(
+            played(&mut world),
+            (1, vec![Sfx::Miss], AnimationState::Idle)
+        );
+    }
+
+    #[test]
+    fn health_bars_wait_for_the_damage() {
+        let cues = vec![Cue::Wait(0.15), Cue::Damage { seconds: 1. }];
+        let roll = Roll {
+            damage: 10,
+            critical: false,
+        };
+        let (mut world, mut schedule) = fight(Sequence::new(Side::Player, cues, roll));
+        world.spawn(opponent(30));
+
+        step(&mut world, &mut schedule);
+        step(&mut world, &mut schedule);
+        assert!(world.resource::<Timeline>().holds_health(Side::Opponent));
+        assert_eq!(shown(&mut world), 40.);
+
+        // the damage pops up, while the cue keeps playing
+        step(&mut world, &mut schedule);
+        assert!(!world.resource::<Timeline>().is_idle());
+        assert!(!world.resource::<Timeline>().holds_health(Side::Opponent));
+        assert!(shown(&mut world) < 40.);
+        for _ in 0..10 {
+            step(&mut world, &mut schedule);
+        }
+        assert_eq!(shown(&mut world), 30.);
+    }
+
+    #[test]
+    fn health_bars_are_never_more_than_full() {
+        let cues = vec![Cue::Wait(10.), Cue::Damage { seconds: 0. }];
+        let roll = Roll {
+            damage: 10,
+            critical: false,
+        };
+        let (mut world, mut schedule) = fight(Sequence::new(Side::Player, cues, roll));
+        let (creature, side, _) = opponent(40);
+        world.spawn((creature, side, HealthDisplay { shown: 55. }));
+        step(&mut world, &mut schedule);
+        assert_eq!(shown(&mut world), 40.);
+    }
+}
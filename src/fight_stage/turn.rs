@@ -0,0 +1,684 @@
+use crate::audio::music::{MusicCue, PlayJingle};
+use crate::audio::sfx::{PlaySfx, Sfx};
+use crate::creature::animations::AnimationState;
+use crate::creature::effects::{Effect, Trigger};
+use crate::creature::moves::Move;
+use crate::creature::{Creature, CreatureTag};
+use crate::data::GameData;
+use crate::dex::Garbagedex;
+use crate::dialogue::StartDialogue;
+use crate::fight_stage::action_bar::BarMenu;
+use crate::fight_stage::animation::{creature_sprite, Animator};
+use crate::fight_stage::timeline::{HealthDisplay, Sequence, Timeline};
+use crate::fight_stage::{Fight, FightLog, Side};
+use crate::flags::Flags;
+use crate::inventory::items::{Item, ItemEffect};
+use crate::inventory::Inventory;
+use crate::overworld::npc::DefeatedTrainers;
+use crate::overworld::PlayerLocation;
+use crate::party::{PartyMember, PlayerParty};
+use crate::GameState;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Coins a beaten opponent is worth per level
+const WILD_COINS_PER_LEVEL: u32 = 3;
+const TRAINER_COINS_PER_LEVEL: u32 = 10;
+
+/// What the player picked for this turn
+pub(super) enum PlayerAction {
+    /// Index in [`Creature::get_moves`]
+    Move(usize),
+    /// Item id
+    Item(String),
+}
+
+/// What is left of a turn once the player acted
+pub(super) enum TurnStep {
+    OpponentMove,
+    EndTurn,
+}
+
+/// Everything a turn may change
+#[derive(SystemParam)]
+pub(super) struct TurnContext<'w, 's> {
+    pub(super) fight: ResMut<'w, Fight>,
+    pub(super) next_state: ResMut<'w, NextState<GameState>>,
+    party: ResMut<'w, PlayerParty>,
+    inventory: ResMut<'w, Inventory>,
+    flags: ResMut<'w, Flags>,
+    pub(super) log: ResMut<'w, FightLog>,
+    pub(super) game_data: GameData<'w>,
+    dex: ResMut<'w, Garbagedex>,
+    start_dialogue: EventWriter<'w, StartDialogue>,
+    jingles: EventWriter<'w, PlayJingle>,
+    sfx: EventWriter<'w, PlaySfx>,
+    timeline: ResMut<'w, Timeline>,
+    creatures: Query<'w, 's, (Entity, &'static mut Creature, &'static Side)>,
+    animators: Query<'w, 's, (Entity, &'static mut Animator, &'static Side)>,
+}
+
+/// How far the player's action got
+enum Outcome {
+    /// Nothing happened, the player can pick something else
+    Nothing,
+    TurnUsed,
+    FightOver,
+}
+
+impl TurnContext<'_, '_> {
+    fn creature(&self, side: Side) -> Option<&Creature> {
+        self.creatures
+            .iter()
+            .find(|(_, _, creature_side)| **creature_side == side)
+            .map(|(_, creature, _)| creature)
+    }
+
+    fn creature_mut(&mut self, side: Side) -> Option<Mut<'_, Creature>> {
+        self.creatures
+            .iter_mut()
+            .find(|(_, _, creature_side)| **creature_side == side)
+            .map(|(_, creature, _)| creature)
+    }
+
+    /// Moves are still playing out or the turn isn't over yet
+    pub(super) fn is_busy(&self) -> bool {
+        !self.timeline.is_idle() || !self.fight.pending.is_empty()
+    }
+
+    fn animate(&mut self, side: Side, state: AnimationState) {
+        for (_, mut animator, animator_side) in &mut self.animators {
+            if *animator_side == side {
+                animator.play(state);
+            }
+        }
+    }
+}
+
+/// The player acts, then the opponent strikes back with a random move if it is still standing
+///
+/// Only the player's action happens right away, the rest of the turn waits for its moves to
+/// play out, see [`continue_turn`].
+pub(super) fn take_turn(turn: &mut TurnContext, action: PlayerAction) {
+    // a fainted creature is replaced before the next turn
+    if turn
+        .creatures
+        .iter()
+        .any(|(_, creature, _)| creature.is_dead())
+    {
+        return;
+    }
+    let outcome = match action {
+        PlayerAction::Move(index) => {
+            let Some(id) = turn
+                .creature(Side::Player)
+                .and_then(|creature| creature.get_moves().get(index).cloned())
+            else {
+                return;
+            };
+            let Some(used_move) = find_move(turn, &id) else {
+                return;
+            };
+            attack(turn, &used_move, Side::Player);
+            Outcome::TurnUsed
+        }
+        PlayerAction::Item(id) => {
+            let Some(item) = find_item(turn, &id) else {
+                return;
+            };
+            use_item(turn, &id, &item)
+        }
+    };
+    if let Outcome::TurnUsed = outcome {
+        turn.fight
+            .pending
+            .extend([TurnStep::OpponentMove, TurnStep::EndTurn]);
+    }
+}
+
+/// Plays the next step of the turn once the moves before it are done
+pub(super) fn continue_turn(mut turn: TurnContext) {
+    if !turn.timeline.is_idle() {
+        return;
+    }
+    let Some(step) = turn.fight.pending.pop_front() else {
+        return;
+    };
+    match step {
+        TurnStep::OpponentMove => opponent_move(&mut turn),
+        TurnStep::EndTurn => end_turn(&mut turn),
+    }
+}
+
+fn opponent_move(turn: &mut TurnContext) {
+    let opponent_moves = turn
+        .creature(Side::Opponent)
+        .filter(|opponent| !opponent.is_dead())
+        .map(|opponent| opponent.get_moves().to_vec())
+        .unwrap_or_default();
+    if !opponent_moves.is_empty() {
+        let opponent_move = &opponent_moves[rand::random::<usize>() % opponent_moves.len()];
+        if let Some(used_move) = find_move(turn, opponent_move) {
+            attack(turn, &used_move, Side::Opponent);
+        }
+    }
+}
+
+fn end_turn(turn: &mut TurnContext) {
+    let mut lines = vec![];
+    for (_, mut creature, _) in &mut turn.creatures {
+        if creature.is_dead() {
+            continue;
+        }
+        let damage = creature.end_turn();
+        if damage > 0 {
+            lines.push(format!("{} suffers {damage} damage", creature.get_name()));
+            lines.extend(creature.trigger_held_item(Trigger::LowHealth));
+        }
+    }
+    for line in lines {
+        turn.log.push(line);
+    }
+
+    // the party remembers the damage and used up items for the next fight
+    if let Some((health, held_item)) = turn.creature(Side::Player).map(|creature| {
+        let held_item = creature
+            .get_held_item()
+            .map(|held_item| held_item.id.clone());
+        (creature.get_health(), held_item)
+    }) {
+        let player_index = turn.fight.player_index;
+        let member = &mut turn.party.members[player_index];
+        member.health = Some(health);
+        member.held_item = held_item;
+    }
+}
+
+/// Clones just the move out of the table, using it needs the whole [`TurnContext`]
+fn find_move(turn: &TurnContext, id: &str) -> Option<Move> {
+    let used_move = turn.game_data.moves()?.get(id);
+    if used_move.is_none() {
+        warn!("Unknown move \"{id}\"");
+    }
+    used_move.cloned()
+}
+
+/// Clones just the item out of the table, using it needs the whole [`TurnContext`]
+fn find_item(turn: &TurnContext, id: &str) -> Option<Item> {
+    let item = turn.game_data.items()?.get(id);
+    if item.is_none() {
+        warn!("Unknown item \"{id}\"");
+    }
+    item.cloned()
+}
+
+fn attack(turn: &mut TurnContext, used_move: &Move, attacker: Side) {
+    let Some(mut creature) = turn.creature_mut(attacker) else {
+        return;
+    };
+    let roll = creature.roll_damage(used_move);
+    let attacker_name = creature.get_name().to_string();
+    let critical_trigger = if roll.critical {
+        creature.trigger_held_item(Trigger::CriticalHit)
+    } else {
+        None
+    };
+    let target = attacker.other();
+    turn.timeline
+        .play(Sequence::new(attacker, used_move.sequence(), roll));
+    let Some(mut creature) = turn.creature_mut(target) else {
+        return;
+    };
+    creature.set_wiggle_period(f32::max(rand::random::<f32>(), 0.2) + 0.05);
+    creature.take_damage(roll.damage);
+    let mut line = format!(
+        "{attacker_name} used {} for {} damage",
+        used_move.name, roll.damage
+    );
+    if roll.critical {
+        line += ", a critical hit";
+    }
+    if let Some((malus, turns)) = used_move.inflicts {
+        creature.add_effect(Effect::Malus(malus), Some(turns));
+        line += &format!(", {} is {}", creature.get_name(), malus.label());
+    }
+    let low_health_trigger = creature.trigger_held_item(Trigger::LowHealth);
+    turn.log.push(line);
+    for line in [critical_trigger, low_health_trigger].into_iter().flatten() {
+        turn.log.push(line);
+    }
+}
+
+fn use_item(turn: &mut TurnContext, id: &str, item: &Item) -> Outcome {
+    if !turn.inventory.items.contains_key(id) {
+        return Outcome::Nothing;
+    }
+    let outcome = match item.effect {
+        None if item.held.is_empty() => Outcome::Nothing,
+        None => hold_item(turn, id, item),
+        Some(ItemEffect::Trap { catch_chance }) => try_catch(turn, catch_chance),
+        Some(ItemEffect::Heal(amount)) => {
+            let Some(mut creature) = turn.creature_mut(Side::Player) else {
+                return Outcome::Nothing;
+            };
+            let healed = creature.heal(amount);
+            let name = creature.get_name().to_string();
+            if healed == 0 {
+                turn.log.push(format!("{name} is already at full health"));
+                Outcome::Nothing
+            } else {
+                turn.log.push(format!("{name} recovered {healed} health"));
+                Outcome::TurnUsed
+            }
+        }
+        Some(ItemEffect::Cure) => {
+            let Some(mut creature) = turn.creature_mut(Side::Player) else {
+                return Outcome::Nothing;
+            };
+            let cured = creature.cure();
+            let name = creature.get_name().to_string();
+            if cured {
+                turn.log.push(format!("{name} feels better"));
+                Outcome::TurnUsed
+            } else {
+                turn.log.push(format!("{name} has nothing to cure"));
+                Outcome::Nothing
+            }
+        }
+        Some(ItemEffect::Boost(bonus, turns)) => {
+            let Some(mut creature) = turn.creature_mut(Side::Player) else {
+                return Outcome::Nothing;
+            };
+            // using the item takes this turn, so the boost lasts one turn longer
+            creature.add_effect(Effect::Bonus(bonus), Some(turns + 1));
+            let line = format!("{} is {}", creature.get_name(), bonus.label());
+            turn.log.push(line);
+            Outcome::TurnUsed
+        }
+    };
+    if !matches!(outcome, Outcome::Nothing) {
+        turn.log.push(format!("Used {}", item.name));
+        turn.inventory.take(id);
+        turn.fight.menu = BarMenu::Moves;
+        turn.fight.redraw_bar = true;
+    }
+    outcome
+}
+
+/// Gives the item to the player's creature, the item it held before goes back into the inventory
+fn hold_item(turn: &mut TurnContext, id: &str, item: &Item) -> Outcome {
+    let Some(mut creature) = turn.creature_mut(Side::Player) else {
+        return Outcome::Nothing;
+    };
+    let previous = creature.hold(id, item);
+    let line = format!("{} now holds {}", creature.get_name(), item.name);
+    turn.log.push(line);
+    if let Some(previous) = previous {
+        turn.inventory.add(&previous, 1);
+    }
+    let player_index = turn.fight.player_index;
+    turn.party.members[player_index].held_item = Some(id.to_string());
+    Outcome::TurnUsed
+}
+
+/// Weakened creatures are easier to catch, up to one and a half times the trap's chance
+fn try_catch(turn: &mut TurnContext, catch_chance: f32) -> Outcome {
+    if turn.fight.trainer.is_some() {
+        turn.log
+            .push("You can't catch someone else's creature!".to_string());
+        return Outcome::Nothing;
+    }
+    if turn.party.is_full() {
+        turn.log.push("There is no room in your party".to_string());
+        return Outcome::Nothing;
+    }
+    let Some(creature) = turn.creature(Side::Opponent) else {
+        return Outcome::Nothing;
+    };
+    let health = creature.get_health() as f32 / creature.get_max_health() as f32;
+    let name = creature.get_name().to_string();
+    if rand::random::<f32>() >= catch_chance * (1.5 - health) {
+        turn.log.push(format!("{name} broke free!"));
+        return Outcome::TurnUsed;
+    }
+    let caught = PartyMember {
+        species: turn.fight.opponent.species.clone(),
+        level: creature.get_level(),
+        nickname: None,
+        moves: Some(creature.get_moves().to_vec()),
+        health: Some(creature.get_health()),
+        held_item: None,
+    };
+    turn.party.members.push(caught);
+    turn.sfx
+        .send(PlaySfx::new(Sfx::Capture).at(Side::Opponent.position()));
+    turn.jingles.send(PlayJingle(MusicCue::Victory));
+    let on_win = turn.fight.on_win.clone();
+    for change in &on_win {
+        turn.flags.apply(change);
+    }
+    turn.start_dialogue.send(StartDialogue::lines(
+        None,
+        vec![format!("You caught {name}!")],
+    ));
+    turn.next_state.set(GameState::Playing);
+    Outcome::FightOver
+}
+
+/// Sends in the next creature of a side once its current one fainted and ends the fight when
+/// there is none left
+pub(super) fn replace_fainted(
+    mut commands: Commands,
+    mut turn: TurnContext,
+    mut defeated_trainers: ResMut<DefeatedTrainers>,
+    mut location: ResMut<PlayerLocation>,
+    asset_server: Res<AssetServer>,
+    tagged: Query<(Entity, &CreatureTag)>,
+    health_displays: Query<&HealthDisplay>,
+) {
+    let Some((entity, fainted_tag, fainted_name, fainted_level, side)) = turn
+        .creatures
+        .iter()
+        .find(|(_, creature, _)| creature.is_dead())
+        .map(|(entity, creature, side)| {
+            (
+                entity,
+                creature.get_creature_tag(),
+                creature.get_name().to_string(),
+                creature.get_level(),
+                *side,
+            )
+        })
+    else {
+        return;
+    };
+    let (Some(species_table), Some(items)) = (turn.game_data.species(), turn.game_data.items())
+    else {
+        return;
+    };
+    // the moves play out and the health bar runs empty before the creature faints
+    if turn.is_busy()
+        || health_displays
+            .get(entity)
+            .is_ok_and(|health_display| health_display.shown > 0.)
+    {
+        return;
+    }
+    // the creature is only replaced once it is done fainting, the other side cheers meanwhile
+    let fainting = turn
+        .animators
+        .iter()
+        .find(|(_, _, animator_side)| **animator_side == side)
+        .map(|(_, animator, _)| (animator.state(), animator.is_finished()));
+    match fainting {
+        Some((AnimationState::Faint, false)) => return,
+        Some((AnimationState::Faint, true)) | None => {}
+        Some(_) => {
+            info!("{fainted_name} fainted");
+            turn.sfx.send(PlaySfx::new(Sfx::Faint).at(side.position()));
+            turn.animate(side, AnimationState::Faint);
+            turn.animate(side.other(), AnimationState::Victory);
+            return;
+        }
+    }
+    if side == Side::Opponent {
+        let coins_per_level = match turn.fight.trainer {
+            Some(_) => TRAINER_COINS_PER_LEVEL,
+            None => WILD_COINS_PER_LEVEL,
+        };
+        turn.fight.prize += fainted_level * coins_per_level;
+    }
+    let next = match side {
+        Side::Opponent => turn.fight.opponents.pop_front(),
+        Side::Player => turn.party.first_able().map(|index| {
+            turn.fight.player_index = index;
+            turn.party.members[index].clone()
+        }),
+    };
+    let Some((member, next, species)) = next.and_then(|member| {
+        let species = species_table.get(&member.species)?.clone();
+        let creature = member.to_creature(species_table, items)?;
+        Some((member, creature, species))
+    }) else {
+        match side {
+            Side::Opponent => win(&mut turn, &mut defeated_trainers),
+            Side::Player => {
+                // black out and wake up at the start of the map with a healed party
+                turn.party.heal();
+                location.tile = None;
+                turn.start_dialogue.send(StartDialogue::lines(
+                    None,
+                    vec![
+                        "All of your creatures fainted...".to_string(),
+                        "You hurry back and patch them up.".to_string(),
+                    ],
+                ));
+            }
+        }
+        turn.next_state.set(GameState::Playing);
+        return;
+    };
+
+    // the plaque, health bar and sprite are reused for the next creature
+    for (tagged_entity, tag) in &tagged {
+        if *tag == fainted_tag {
+            commands
+                .entity(tagged_entity)
+                .insert(next.get_creature_tag());
+        }
+    }
+    for (sprite, _, sprite_side) in &turn.animators {
+        if *sprite_side == side {
+            commands
+                .entity(sprite)
+                .remove::<(TextureAtlasSprite, Handle<TextureAtlas>)>()
+                .insert(creature_sprite(&species, &asset_server));
+        }
+    }
+    turn.animate(side.other(), AnimationState::Idle);
+    let line = match (side, &turn.fight.trainer) {
+        (Side::Opponent, Some(trainer)) => {
+            format!("{} sends out {}!", trainer.name, next.get_name())
+        }
+        _ => format!("Go, {}!", next.get_name()),
+    };
+    turn.log.push(line);
+    match side {
+        Side::Opponent => {
+            turn.dex.see(&member.species);
+            turn.fight.opponent = member;
+        }
+        Side::Player => turn.fight.redraw_bar = true,
+    }
+    commands
+        .entity(entity)
+        .insert((HealthDisplay::new(&next), next));
+}
+
+fn win(turn: &mut TurnContext, defeated_trainers: &mut DefeatedTrainers) {
+    turn.jingles.send(PlayJingle(MusicCue::Victory));
+    let on_win = turn.fight.on_win.clone();
+    for change in &on_win {
+        turn.flags.apply(change);
+    }
+    let prize = turn.fight.prize;
+    turn.inventory.money = turn.inventory.money.saturating_add(prize);
+    let Some(trainer) = &turn.fight.trainer else {
+        turn.start_dialogue.send(StartDialogue::lines(
+            None,
+            vec![format!("You found {prize} coins in the trash.")],
+        ));
+        return;
+    };
+    defeated_trainers.0.insert(trainer.id.clone());
+    let mut lines = trainer.defeated.clone();
+    lines.push(format!("Here, take these {prize} coins."));
+    turn.start_dialogue
+        .send(StartDialogue::lines(Some(&trainer.name), lines));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::creature::effects::Malus;
+    use crate::creature::moves::{Cue, MoveTable, Roll};
+    use crate::creature::species::{Species, SpeciesTable};
+    use crate::inventory::items::ItemTable;
+    use crate::loading::DataAssets;
+    use crate::shop::stock::ShopTable;
+    use bevy::asset::AssetPlugin;
+    use std::collections::VecDeque;
+
+    fn binrat() -> Species {
+        Species {
+            name: "Binrat".to_string(),
+            description: String::new(),
+            elements: vec![],
+            sprite: String::new(),
+            animations: None,
+            base_health: 40,
+            health_per_level: 0,
+            moves: vec![],
+        }
+    }
+
+    /// Just the resources a turn needs, with empty data tables
+    fn fight() -> App {
+        let mut app = App::new();
+        app.add_plugin(TaskPoolPlugin::default())
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<SpeciesTable>()
+            .add_asset::<MoveTable>()
+            .add_asset::<ItemTable>()
+            .add_asset::<ShopTable>()
+            .add_state::<GameState>()
+            .add_event::<StartDialogue>()
+            .add_event::<PlayJingle>()
+            .add_event::<PlaySfx>();
+        let world = &mut app.world;
+        let species = world
+            .resource_mut::<Assets<SpeciesTable>>()
+            .add(SpeciesTable {
+                species: [("binrat".to_string(), binrat())].into(),
+            });
+        let items = world.resource_mut::<Assets<ItemTable>>().add(ItemTable {
+            items: Default::default(),
+        });
+        world.insert_resource(DataAssets {
+            species,
+            moves: Handle::default(),
+            items,
+            shops: Handle::default(),
+            quests: Handle::default(),
+        });
+        world.insert_resource(Fight {
+            trainer: None,
+            on_win: vec![],
+            opponent: PartyMember::wild("binrat", 1),
+            opponents: VecDeque::new(),
+            player_index: 0,
+            menu: BarMenu::Moves,
+            redraw_bar: false,
+            prize: 0,
+            pending: VecDeque::new(),
+        });
+        world.insert_resource(PlayerParty {
+            members: vec![PartyMember::wild("binrat", 1)],
+        });
+        world.insert_resource(Inventory::default());
+        world.insert_resource(Flags::default());
+        world.insert_resource(FightLog::default());
+        world.insert_resource(Garbagedex::default());
+        world.insert_resource(Timeline::default());
+        for side in [Side::Player, Side::Opponent] {
+            world.spawn((
+                Creature::new(&binrat(), 1),
+                side,
+                HealthDisplay { shown: 40. },
+                Animator::new(None),
+            ));
+        }
+        app
+    }
+
+    /// A move still playing, which takes far longer than the test
+    fn playing() -> Sequence {
+        let roll = Roll {
+            damage: 1,
+            critical: false,
+        };
+        Sequence::new(Side::Player, vec![Cue::Wait(100.)], roll)
+    }
+
+    fn player<'a>(app: &'a mut App) -> Mut<'a, Creature> {
+        let mut query = app.world.query::<(&mut Creature, &Side)>();
+        query
+            .iter_mut(&mut app.world)
+            .find(|(_, side)| **side == Side::Player)
+            .map(|(creature, _)| creature)
+            .unwrap()
+    }
+
+    fn animation(app: &mut App, side: Side) -> AnimationState {
+        let mut query = app.world.query::<(&Animator, &Side)>();
+        query
+            .iter(&app.world)
+            .find(|(_, animator_side)| **animator_side == side)
+            .map(|(animator, _)| animator.state())
+            .unwrap()
+    }
+
+    #[test]
+    fn turns_wait_for_the_timeline() {
+        let mut app = fight();
+        app.add_system(continue_turn);
+        player(&mut app).add_effect(Effect::Malus(Malus::LoseHealth(3)), Some(2));
+        app.world.resource_mut::<Timeline>().play(playing());
+        app.world
+            .resource_mut::<Fight>()
+            .pending
+            .extend([TurnStep::OpponentMove, TurnStep::EndTurn]);
+
+        app.update();
+        app.update();
+        assert_eq!(app.world.resource::<Fight>().pending.len(), 2);
+        assert_eq!(player(&mut app).get_health(), 40);
+
+        *app.world.resource_mut::<Timeline>() = Timeline::default();
+        app.update();
+        assert_eq!(app.world.resource::<Fight>().pending.len(), 1);
+        assert_eq!(player(&mut app).get_health(), 40);
+        app.update();
+        assert!(app.world.resource::<Fight>().pending.is_empty());
+        assert_eq!(player(&mut app).get_health(), 37);
+        assert_eq!(
+            app.world.resource::<PlayerParty>().members[0].health,
+            Some(37)
+        );
+    }
+
+    #[test]
+    fn fainting_waits_for_the_health_bar() {
+        let mut app = fight();
+        app.insert_resource(DefeatedTrainers::default())
+            .insert_resource(PlayerLocation {
+                map: Handle::default(),
+                tile: None,
+            })
+            .add_system(replace_fainted);
+        player(&mut app).take_damage(40);
+
+        app.update();
+        assert_eq!(animation(&mut app, Side::Player), AnimationState::Idle);
+
+        let mut query = app.world.query::<(&mut HealthDisplay, &Side)>();
+        for (mut display, side) in query.iter_mut(&mut app.world) {
+            if *side == Side::Player {
+                display.shown = 0.;
+            }
+        }
+        app.update();
+        assert_eq!(animation(&mut app, Side::Player), AnimationState::Faint);
+        assert_eq!(animation(&mut app, Side::Opponent), AnimationState::Victory);
+    }
+}
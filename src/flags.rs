@@ -0,0 +1,158 @@
+use crate::quests::Quests;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Named story flags, changed by dialogue, fights and warps and checked by [`Condition`]s
+///
+/// Every flag is a number, so it can count things as well. Zero counts as not set.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Flags(pub BTreeMap<String, i32>);
+
+impl Flags {
+    pub fn get(&self, flag: &str) -> i32 {
+        self.0.get(flag).copied().unwrap_or_default()
+    }
+
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.get(flag) != 0
+    }
+
+    pub fn apply(&mut self, change: &FlagChange) {
+        match change {
+            FlagChange::Set(flag) => {
+                self.0.insert(flag.clone(), 1);
+            }
+            FlagChange::Clear(flag) => {
+                self.0.remove(flag);
+            }
+            FlagChange::Add(flag, amount) => {
+                *self.0.entry(flag.clone()).or_default() += amount;
+            }
+            FlagChange::Assign(flag, value) => {
+                self.0.insert(flag.clone(), *value);
+            }
+        }
+    }
+}
+
+/// A change to one flag, as written in map and dialogue files
+#[derive(Deserialize, Clone, Debug)]
+pub enum FlagChange {
+    Set(String),
+    Clear(String),
+    Add(String, i32),
+    Assign(String, i32),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum Condition {
+    Flag(String),
+    NotFlag(String),
+    /// The flag's value is at least this much
+    AtLeast(String, i32),
+    /// The flag's value is less than this
+    Below(String, i32),
+    QuestActive(String),
+    QuestCompleted(String),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn is_met(&self, flags: &Flags, quests: &Quests) -> bool {
+        match self {
+            Condition::Flag(flag) => flags.is_set(flag),
+            Condition::NotFlag(flag) => !flags.is_set(flag),
+            Condition::AtLeast(flag, value) => flags.get(flag) >= *value,
+            Condition::Below(flag, value) => flags.get(flag) < *value,
+            Condition::QuestActive(quest) => quests.is_active(quest),
+            Condition::QuestCompleted(quest) => quests.is_completed(quest),
+            Condition::Not(condition) => !condition.is_met(flags, quests),
+            Condition::All(conditions) => conditions
+                .iter()
+                .all(|condition| condition.is_met(flags, quests)),
+            Condition::Any(conditions) => conditions
+                .iter()
+                .any(|condition| condition.is_met(flags, quests)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(name: &str) -> String {
+        name.to_string()
+    }
+
+    #[test]
+    fn conditions_check_flags_and_quests() {
+        let mut flags = Flags::default();
+        flags.apply(&FlagChange::Set(flag("badge")));
+        flags.apply(&FlagChange::Add(flag("cans"), 2));
+        let mut quests = Quests::default();
+        quests.start("beach");
+        quests.start("dump");
+        quests.complete("dump");
+
+        let cases = [
+            (Condition::Flag(flag("badge")), true),
+            (Condition::Flag(flag("unknown")), false),
+            (Condition::NotFlag(flag("badge")), false),
+            (Condition::NotFlag(flag("unknown")), true),
+            (Condition::AtLeast(flag("cans"), 2), true),
+            (Condition::AtLeast(flag("cans"), 3), false),
+            (Condition::Below(flag("cans"), 3), true),
+            (Condition::Below(flag("cans"), 2), false),
+            (Condition::QuestActive(flag("beach")), true),
+            (Condition::QuestActive(flag("dump")), false),
+            (Condition::QuestCompleted(flag("dump")), true),
+            (Condition::QuestCompleted(flag("beach")), false),
+            (
+                Condition::Not(Box::new(Condition::Flag(flag("badge")))),
+                false,
+            ),
+            (
+                Condition::All(vec![
+                    Condition::Flag(flag("badge")),
+                    Condition::QuestActive(flag("beach")),
+                ]),
+                true,
+            ),
+            (
+                Condition::All(vec![
+                    Condition::Flag(flag("badge")),
+                    Condition::Flag(flag("unknown")),
+                ]),
+                false,
+            ),
+            (Condition::All(vec![]), true),
+            (
+                Condition::Any(vec![
+                    Condition::Flag(flag("unknown")),
+                    Condition::QuestCompleted(flag("dump")),
+                ]),
+                true,
+            ),
+            (Condition::Any(vec![]), false),
+        ];
+        for (condition, expected) in cases {
+            assert_eq!(condition.is_met(&flags, &quests), expected, "{condition:?}");
+        }
+    }
+
+    #[test]
+    fn flags_count_and_clear() {
+        let mut flags = Flags::default();
+        flags.apply(&FlagChange::Add(flag("cans"), 2));
+        flags.apply(&FlagChange::Add(flag("cans"), -2));
+        assert!(!flags.is_set("cans"));
+        flags.apply(&FlagChange::Assign(flag("cans"), 5));
+        assert_eq!(flags.get("cans"), 5);
+        flags.apply(&FlagChange::Clear(flag("cans")));
+        assert_eq!(flags.get("cans"), 0);
+    }
+}
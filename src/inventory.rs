@@ -0,0 +1,74 @@
+use crate::data::RonAssetApp;
+use crate::inventory::items::ItemTable;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub mod items;
+
+pub struct InventoryPlugin;
+
+/// This plugin registers the item definitions, see [`ItemTable`]
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<ItemTable>();
+    }
+}
+
+/// The items the player carries, as item id and count, and the player's coins
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct Inventory {
+    pub items: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub money: u32,
+}
+
+/// What a new game starts with
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory {
+            items: BTreeMap::from([("potion".to_string(), 3), ("trash_trap".to_string(), 3)]),
+            money: 100,
+        }
+    }
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: &str, count: u32) {
+        *self.items.entry(item.to_string()).or_default() += count;
+    }
+
+    /// Takes one of the item out of the inventory, returns `false` if there is none
+    pub fn take(&mut self, item: &str) -> bool {
+        let Some(count) = self.items.get_mut(item) else {
+            return false;
+        };
+        // a count of 0 (like in a hand edited save) is as good as none
+        let Some(left) = count.checked_sub(1) else {
+            self.items.remove(item);
+            return false;
+        };
+        *count = left;
+        if left == 0 {
+            self.items.remove(item);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taking_the_last_item_removes_it() {
+        let mut inventory = Inventory {
+            items: BTreeMap::from([("potion".to_string(), 1), ("broken".to_string(), 0)]),
+            money: 0,
+        };
+        assert!(inventory.take("potion"));
+        assert!(!inventory.take("potion"));
+        assert!(!inventory.take("broken"));
+        assert!(inventory.items.is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::creature::effects::{Bonus, HeldEffect};
+use crate::data::RonAsset;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Every item in the game, keyed by item id
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "5c7a2e91-0f4b-4d38-a6e2-9b1d3c8f7a40"]
+pub struct ItemTable {
+    pub items: BTreeMap<String, Item>,
+}
+
+impl RonAsset for ItemTable {
+    const EXTENSIONS: &'static [&'static str] = &["items.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.items.extend(patch.items);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Item {
+    pub name: String,
+    pub description: String,
+    /// What shops pay for the item if they don't stock it themselves, `0` if they won't take it
+    #[serde(default)]
+    pub value: u32,
+    /// What using the item in a fight does, `None` for items that can only be held
+    #[serde(default)]
+    pub effect: Option<ItemEffect>,
+    /// What the item does while a creature holds it, items without any can't be held
+    #[serde(default)]
+    pub held: Vec<HeldEffect>,
+    /// Shown on the fight plaque of the creature holding the item
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+/// What using an item in a fight does
+#[derive(Deserialize, Clone, Debug)]
+pub enum ItemEffect {
+    /// Restores this much health of the player's creature
+    Heal(u32),
+    /// Removes all maluses from the player's creature
+    Cure,
+    /// Gives the player's creature a bonus for this many turns
+    Boost(Bonus, u32),
+    /// Tries to catch a wild creature, weakened creatures are easier to catch
+    Trap { catch_chance: f32 },
+}
+
+impl ItemTable {
+    pub fn get(&self, id: &str) -> Option<&Item> {
+        self.items.get(id)
+    }
+}
@@ -1,15 +1,48 @@
 mod actions;
 mod audio;
 mod creature;
+mod data;
+mod dex;
+mod dialogue;
 mod fight_stage;
+mod flags;
+mod inventory;
 mod loading;
+mod mods;
+mod overworld;
+mod party;
+mod pause_menu;
+mod persistence;
 mod player;
+mod quests;
+mod save;
+mod settings;
+mod shop;
+mod title;
+mod ui;
+#[cfg(test)]
+mod validation;
 
 use crate::actions::ActionsPlugin;
 use crate::audio::InternalAudioPlugin;
+use crate::creature::CreaturePlugin;
+use crate::dex::DexPlugin;
+use crate::dialogue::DialoguePlugin;
 use crate::fight_stage::MenuPlugin;
+use crate::inventory::InventoryPlugin;
 use crate::loading::LoadingPlugin;
+use crate::mods::ModsPlugin;
+use crate::overworld::OverworldPlugin;
+use crate::pause_menu::PausePlugin;
 use crate::player::PlayerPlugin;
+use crate::quests::QuestPlugin;
+use crate::settings::SettingsPlugin;
+use crate::shop::ShopPlugin;
+use crate::title::TitlePlugin;
+use crate::ui::UiPlugin;
+
+pub use crate::mods::io::ModAssetIoPlugin;
+pub use crate::settings::Settings;
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
@@ -24,22 +57,48 @@ enum GameState {
     // During the loading State the LoadingPlugin will load our assets
     #[default]
     Loading,
-    // During this State the actual game logic is executed
+    // The title screen, shown after loading and when quitting from the pause menu
+    Title,
+    // Walking around the overworld
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Fight,
 }
 
+// Layered on top of `GameState`: while paused, everything scheduled in
+// `OnUpdate(GameState::Playing)` and `OnUpdate(GameState::Fight)` is skipped
+// and `Time` is frozen, so the pause menu can be drawn over the frozen scene
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<GameState>()
+            .add_state::<PauseState>()
+            .add_startup_system(spawn_camera)
             .add_plugin(LoadingPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(ModsPlugin)
+            .add_plugin(UiPlugin)
+            .add_plugin(TitlePlugin)
             .add_plugin(MenuPlugin)
+            .add_plugin(PausePlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(InternalAudioPlugin)
-            .add_plugin(PlayerPlugin);
+            .add_plugin(PlayerPlugin)
+            .add_plugin(OverworldPlugin)
+            .add_plugin(CreaturePlugin)
+            .add_plugin(DialoguePlugin)
+            .add_plugin(QuestPlugin)
+            .add_plugin(InventoryPlugin)
+            .add_plugin(ShopPlugin)
+            .add_plugin(DexPlugin);
 
         #[cfg(debug_assertions)]
         {
@@ -49,6 +108,10 @@ impl Plugin for GamePlugin {
     }
 }
 
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
 pub fn random_dice() {
     let roll = rand::random::<u32>() % 7;
     println!("{}", roll);
@@ -1,21 +1,40 @@
+use crate::audio::music::MusicTable;
+use crate::audio::sfx::SoundTable;
+use crate::creature::moves::MoveTable;
+use crate::creature::species::SpeciesTable;
+use crate::dialogue::script::DialogueScript;
+use crate::inventory::items::ItemTable;
+use crate::loading::screen::LoadingScreenPlugin;
+use crate::overworld::map::Map;
+use crate::quests::QuestTable;
+use crate::shop::stock::ShopTable;
+use crate::ui::theme::Theme;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
-// use bevy_kira_audio::AudioSource;
+use bevy_kira_audio::AudioSource;
+
+mod screen;
 
 pub struct LoadingPlugin;
 
 /// This plugin loads all assets using [`AssetLoader`] from a third party bevy plugin
 /// Alternatively you can write the logic to load assets yourself
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
+/// Meanwhile a loading screen shows the progress, see [`LoadingScreenPlugin`]
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
         app.add_loading_state(
-            LoadingState::new(GameState::Loading).continue_to_state(GameState::Fight),
+            LoadingState::new(GameState::Loading).continue_to_state(GameState::Title),
         )
         .add_collection_to_loading_state::<_, FontAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, UiAssets>(GameState::Loading)
         .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading);
+        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, MapAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, DataAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, DialogueAssets>(GameState::Loading)
+        .add_plugin(LoadingScreenPlugin);
     }
 }
 
@@ -28,14 +47,50 @@ pub struct FontAssets {
     pub fira_sans: Handle<Font>,
 }
 
+#[derive(AssetCollection, Resource)]
+pub struct UiAssets {
+    #[asset(path = "ui/base.theme.ron")]
+    pub theme: Handle<Theme>,
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct AudioAssets {
-    // #[asset(path = "audio/flying.ogg")]
-    // pub flying: Handle<AudioSource>,
+    #[asset(path = "audio/flying.ogg")]
+    pub flying: Handle<AudioSource>,
+    #[asset(path = "audio/base.music.ron")]
+    pub music: Handle<MusicTable>,
+    #[asset(path = "audio/base.sfx.ron")]
+    pub sfx: Handle<SoundTable>,
 }
 
 #[derive(AssetCollection, Resource)]
 pub struct TextureAssets {
-    #[asset(path = "textures/creatures/gargoylemon.png")]
-    pub gargoylemon: Handle<Image>,
+    #[asset(path = "textures/bevy.png")]
+    pub texture_bevy: Handle<Image>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct MapAssets {
+    #[asset(path = "maps/town.map.ron")]
+    pub town: Handle<Map>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct DataAssets {
+    #[asset(path = "data/base.species.ron")]
+    pub species: Handle<SpeciesTable>,
+    #[asset(path = "data/base.moves.ron")]
+    pub moves: Handle<MoveTable>,
+    #[asset(path = "data/base.items.ron")]
+    pub items: Handle<ItemTable>,
+    #[asset(path = "data/base.shops.ron")]
+    pub shops: Handle<ShopTable>,
+    #[asset(path = "data/base.quests.ron")]
+    pub quests: Handle<QuestTable>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct DialogueAssets {
+    #[asset(paths("dialogue/town.dialogue.ron"), collection(typed))]
+    pub scripts: Vec<Handle<DialogueScript>>,
 }
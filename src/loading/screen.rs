@@ -0,0 +1,219 @@
+use crate::loading::{
+    AudioAssets, DataAssets, DialogueAssets, FontAssets, MapAssets, TextureAssets, UiAssets,
+};
+use crate::GameState;
+use bevy::asset::{HandleId, LoadState};
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+
+/// The font of the loading screen, it can't wait for [`FontAssets`]
+const FONT: &str = "fonts/FiraSans-Bold.ttf";
+const TIP_DURATION: f32 = 4.;
+const TIPS: &[&str] = &[
+    "Weakened creatures are much easier to catch.",
+    "Trainers pay better than the trash you find in the tall grass.",
+    "A held item can turn a fight around, try giving one to your creature.",
+    "Shops buy back items, but never for what they sold them.",
+    "The Garbagedex shows where a species lives once you have seen it.",
+    "Your creatures keep their wounds between fights, patch them up with potions.",
+];
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const ERROR_COLOR: Color = Color::rgb(0.9, 0.35, 0.3);
+const BAR_COLOR: Color = Color::rgb(0.4, 0.7, 0.35);
+const BAR_BACKGROUND: Color = Color::rgb(0.15, 0.15, 0.15);
+
+pub struct LoadingScreenPlugin;
+
+/// This plugin shows the progress of every asset collection while `GameState::Loading` lasts,
+/// together with a gameplay tip every few seconds
+/// If an asset fails to load, the screen says which one instead of waiting forever
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (track_collections, setup_loading_screen).in_schedule(OnEnter(GameState::Loading)),
+        )
+        .add_systems(
+            (update_progress, rotate_tips)
+                .chain()
+                .in_set(OnUpdate(GameState::Loading)),
+        )
+        .add_system(cleanup_loading_screen.in_schedule(OnExit(GameState::Loading)));
+    }
+}
+
+/// Every handle the asset collections are waiting for
+#[derive(Resource)]
+struct LoadingProgress {
+    handles: Vec<HandleUntyped>,
+    failed: bool,
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct ProgressBar;
+
+#[derive(Component)]
+struct ProgressText;
+
+#[derive(Component)]
+struct TipText {
+    index: usize,
+    timer: Timer,
+}
+
+/// Loading is shared with the asset collections, so requesting the same assets here only hands
+/// out more handles to them
+fn track_collections(world: &mut World) {
+    let mut handles = vec![];
+    handles.extend(FontAssets::load(world));
+    handles.extend(UiAssets::load(world));
+    handles.extend(AudioAssets::load(world));
+    handles.extend(TextureAssets::load(world));
+    handles.extend(MapAssets::load(world));
+    handles.extend(DataAssets::load(world));
+    handles.extend(DialogueAssets::load(world));
+    world.insert_resource(LoadingProgress {
+        handles,
+        failed: false,
+    });
+}
+
+fn setup_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load(FONT);
+    let text_style = TextStyle {
+        font,
+        font_size: 28.0,
+        color: TEXT_COLOR,
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(LoadingScreen)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section("Loading...", text_style.clone()))
+                .insert(ProgressText);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(400.0), Val::Px(24.0)),
+                        margin: UiRect::vertical(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: BAR_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(ProgressBar);
+                });
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        TIPS[0],
+                        TextStyle {
+                            font_size: 22.0,
+                            ..text_style
+                        },
+                    )
+                    .with_style(Style {
+                        max_size: Size::width(Val::Px(600.0)),
+                        ..default()
+                    }),
+                )
+                .insert(TipText {
+                    index: 0,
+                    timer: Timer::from_seconds(TIP_DURATION, TimerMode::Repeating),
+                });
+        });
+}
+
+fn update_progress(
+    mut progress: ResMut<LoadingProgress>,
+    asset_server: Res<AssetServer>,
+    mut bar: Query<&mut Style, With<ProgressBar>>,
+    mut text: Query<&mut Text, With<ProgressText>>,
+) {
+    if progress.failed {
+        return;
+    }
+    let states: Vec<(HandleId, LoadState)> = progress
+        .handles
+        .iter()
+        .map(|handle| (handle.id(), asset_server.get_load_state(handle.id())))
+        .collect();
+    let failed: Vec<String> = states
+        .iter()
+        .filter(|(_, state)| *state == LoadState::Failed)
+        .map(|(id, _)| {
+            asset_server.get_handle_path(*id).map_or_else(
+                || format!("{id:?}"),
+                |path| path.path().display().to_string(),
+            )
+        })
+        .collect();
+    let loaded = states
+        .iter()
+        .filter(|(_, state)| *state == LoadState::Loaded)
+        .count();
+    let total = states.len().max(1);
+    for mut style in &mut bar {
+        style.size.width = Val::Percent(100.0 * loaded as f32 / total as f32);
+    }
+    for mut text in &mut text {
+        if failed.is_empty() {
+            text.sections[0].value = format!("Loading... {loaded}/{total}");
+        } else {
+            error!("Failed to load {}", failed.join(", "));
+            text.sections[0].value = format!(
+                "Could not load {}\nCheck that the assets folder is complete and restart the game.",
+                failed.join(", ")
+            );
+            text.sections[0].style.color = ERROR_COLOR;
+            progress.failed = true;
+        }
+    }
+}
+
+fn rotate_tips(
+    time: Res<Time>,
+    progress: Res<LoadingProgress>,
+    mut tips: Query<(&mut Text, &mut TipText)>,
+) {
+    for (mut text, mut tip) in &mut tips {
+        if progress.failed {
+            text.sections[0].value.clear();
+            continue;
+        }
+        if tip.timer.tick(time.delta()).just_finished() {
+            tip.index = (tip.index + 1) % TIPS.len();
+            text.sections[0].value = TIPS[tip.index].to_string();
+        }
+    }
+}
+
+fn cleanup_loading_screen(mut commands: Commands, screen: Query<Entity, With<LoadingScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<LoadingProgress>();
+}
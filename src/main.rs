@@ -5,29 +5,34 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
-use garbagemon::GamePlugin;
+use garbagemon::{GamePlugin, ModAssetIoPlugin, Settings};
 use std::io::Cursor;
 use winit::window::Icon;
 
 fn main() {
+    let asset_plugin = AssetPlugin {
+        // edited data files are reloaded while the game runs
+        watch_for_changes: cfg!(debug_assertions),
+        ..default()
+    };
+    // the saved display settings are used for the window right away
+    let settings = Settings::load();
+    let window = settings.window("Garbagemon!"); // ToDo
     App::new()
+        .insert_resource(settings)
         .insert_resource(Msaa::Sample8)
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Garbagemon!".to_string(), // ToDo
-                resolution: (1024., 720.).into(),
-                resizable: false,
-                // resize_constraints: WindowResizeConstraints {
-                //     min_width: 1024.,
-                //     min_height: 720.,
-                //     ..default()
-                // },
-                canvas: Some("#bevy".to_owned()),
-                ..default()
-            }),
-            ..default()
-        }))
+        .add_plugins(
+            DefaultPlugins
+                .build()
+                .set(asset_plugin.clone())
+                // enabled content packs override files of the `assets` folder
+                .add_before::<AssetPlugin, _>(ModAssetIoPlugin(asset_plugin))
+                .set(WindowPlugin {
+                    primary_window: Some(window),
+                    ..default()
+                }),
+        )
         .add_plugin(GamePlugin)
         .add_system(set_window_icon.on_startup())
         .run();
@@ -0,0 +1,80 @@
+use crate::mods::manifest::ModPack;
+use crate::mods::menu::ModsMenuPlugin;
+use crate::persistence;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub mod io;
+pub mod manifest;
+pub mod menu;
+
+const MOD_SETTINGS_FILE: &str = "mods.ron";
+
+pub struct ModsPlugin;
+
+/// This plugin remembers which content packs are enabled and in which order they load
+/// The packs themselves are found and loaded on start, see [`io::ModAssetIoPlugin`]
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ModSettings>()
+            .init_resource::<InstalledMods>()
+            .init_resource::<ActiveMods>()
+            .add_plugin(ModsMenuPlugin)
+            .add_system(store_mod_settings.run_if(resource_changed::<ModSettings>()));
+    }
+}
+
+/// Ids of the enabled packs in load order, a pack overrides the ones before it
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ModSettings {
+    pub enabled: Vec<String>,
+}
+
+/// Every pack in the mods folder, whether it is enabled or not
+#[derive(Resource, Default)]
+pub struct InstalledMods {
+    pub packs: Vec<ModPack>,
+    /// Why a pack is broken or can't be loaded, keyed by pack id
+    pub problems: BTreeMap<String, String>,
+}
+
+/// The packs loaded on start in load order
+/// Changing the [`ModSettings`] only takes effect after a restart
+#[derive(Resource, Default, Clone)]
+pub struct ActiveMods {
+    pub packs: Vec<ModPack>,
+}
+
+impl ModSettings {
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.enabled.iter().any(|enabled| enabled == id)
+    }
+
+    pub fn toggle(&mut self, id: &str) {
+        if self.is_enabled(id) {
+            self.enabled.retain(|enabled| enabled != id);
+        } else {
+            self.enabled.push(id.to_string());
+        }
+    }
+
+    /// Loads the pack one step earlier, so the packs after it can override it
+    pub fn load_earlier(&mut self, id: &str) {
+        if let Some(index) = self
+            .enabled
+            .iter()
+            .position(|enabled| enabled == id)
+            .filter(|index| *index > 0)
+        {
+            self.enabled.swap(index, index - 1);
+        }
+    }
+}
+
+fn store_mod_settings(mod_settings: Res<ModSettings>) {
+    if let Err(error) = persistence::store(MOD_SETTINGS_FILE, &*mod_settings) {
+        warn!("Failed to store the mod settings: {error}");
+    }
+}
@@ -0,0 +1,251 @@
+use crate::mods::manifest::{discover, resolve, ModPack};
+use crate::mods::{ActiveMods, InstalledMods, ModSettings};
+use crate::persistence;
+use bevy::asset::{AssetIo, AssetIoError, BoxedFuture, FileAssetIo, Metadata};
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Paths starting with this always point into the base `assets` folder
+pub const BASE_PREFIX: &str = "base";
+/// `mods/<id>/<path>` points to a file of the pack with that id
+pub const MODS_PREFIX: &str = "mods";
+
+/// Folder next to `assets` that content packs are dropped into
+const MODS_FOLDER: &str = "mods";
+
+/// This plugin finds the installed content packs and lets the enabled ones override files of
+/// the base `assets` folder, see [`ModAssetIo`]
+/// It has to be added before bevy's `AssetPlugin`, which is given to it for its settings
+pub struct ModAssetIoPlugin(pub AssetPlugin);
+
+impl Plugin for ModAssetIoPlugin {
+    fn build(&self, app: &mut App) {
+        let settings: ModSettings = persistence::load_or_default(super::MOD_SETTINGS_FILE);
+        // the web build can't read folders next to the game
+        let (installed, mut problems) = if cfg!(target_arch = "wasm32") {
+            Default::default()
+        } else {
+            discover(&FileAssetIo::get_base_path().join(MODS_FOLDER))
+        };
+        let (active, resolve_problems) = resolve(&installed, &settings.enabled);
+        problems.extend(resolve_problems);
+        for (id, problem) in &problems {
+            warn!("Mod \"{id}\" {problem}");
+        }
+        for pack in &active {
+            info!(
+                "Loading mod \"{}\" {}",
+                pack.manifest.id, pack.manifest.version
+            );
+        }
+        if !active.is_empty() {
+            app.insert_resource(AssetServer::new(ModAssetIo {
+                base: self.0.create_platform_default_asset_io(),
+                packs: active.clone(),
+            }));
+        }
+        app.insert_resource(settings)
+            .insert_resource(InstalledMods {
+                packs: installed,
+                problems,
+            })
+            .insert_resource(ActiveMods { packs: active });
+    }
+}
+
+/// Reads files from the enabled packs before falling back to the base `assets` folder
+///
+/// The pack loaded last wins. Data tables are merged instead of replaced by their loader,
+/// which reads every layer through [`BASE_PREFIX`] and [`MODS_PREFIX`].
+pub struct ModAssetIo {
+    base: Box<dyn AssetIo>,
+    /// In load order
+    packs: Vec<ModPack>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Layer<'a> {
+    Base(&'a Path),
+    Pack(PathBuf),
+}
+
+impl ModAssetIo {
+    fn layer<'a>(&self, path: &'a Path) -> Layer<'a> {
+        if let Ok(relative) = path.strip_prefix(BASE_PREFIX) {
+            return Layer::Base(relative);
+        }
+        if let Ok(relative) = path.strip_prefix(MODS_PREFIX) {
+            let mut components = relative.components();
+            let id = components.next().map(|id| id.as_os_str().to_string_lossy());
+            if let Some(pack) = self
+                .packs
+                .iter()
+                .find(|pack| Some(pack.manifest.id.as_str()) == id.as_deref())
+            {
+                return Layer::Pack(pack.folder.join(components.as_path()));
+            }
+        }
+        self.packs
+            .iter()
+            .rev()
+            .map(|pack| pack.folder.join(path))
+            .find(|file| file.is_file())
+            .map_or(Layer::Base(path), Layer::Pack)
+    }
+}
+
+/// The entries of `folder`, as paths below `path` like the ones the base `AssetIo` returns
+fn list_folder(folder: &Path, path: &Path) -> Result<Vec<PathBuf>, AssetIoError> {
+    Ok(std::fs::read_dir(folder)?
+        .flatten()
+        .map(|entry| path.join(entry.file_name()))
+        .collect())
+}
+
+impl AssetIo for ModAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        match self.layer(path) {
+            Layer::Base(path) => self.base.load_path(path),
+            Layer::Pack(file) => Box::pin(async move {
+                std::fs::read(&file).map_err(|error| match error.kind() {
+                    std::io::ErrorKind::NotFound => AssetIoError::NotFound(file),
+                    _ => AssetIoError::Io(error),
+                })
+            }),
+        }
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        match self.layer(path) {
+            // a folder of one layer, like `base/textures` or `mods/<id>/data`
+            Layer::Base(relative) if relative != path => {
+                let entries: Vec<PathBuf> = self
+                    .base
+                    .read_directory(relative)?
+                    .map(|entry| Path::new(BASE_PREFIX).join(entry))
+                    .collect();
+                Ok(Box::new(entries.into_iter()))
+            }
+            Layer::Pack(folder) => Ok(Box::new(list_folder(&folder, path)?.into_iter())),
+            // every layer adds its files, files in several layers are only listed once
+            Layer::Base(_) => {
+                let packs: Vec<Vec<PathBuf>> = self
+                    .packs
+                    .iter()
+                    .filter_map(|pack| list_folder(&pack.folder.join(path), path).ok())
+                    .collect();
+                let mut entries = BTreeSet::new();
+                match self.base.read_directory(path) {
+                    Ok(base) => entries.extend(base),
+                    // the folder may only exist in packs
+                    Err(error) if packs.is_empty() => return Err(error),
+                    Err(_) => {}
+                }
+                entries.extend(packs.into_iter().flatten());
+                Ok(Box::new(entries.into_iter()))
+            }
+        }
+    }
+
+    fn get_metadata(&self, path: &Path) -> Result<Metadata, AssetIoError> {
+        match self.layer(path) {
+            Layer::Base(path) => self.base.get_metadata(path),
+            Layer::Pack(file) => {
+                let metadata =
+                    std::fs::metadata(&file).map_err(|_| AssetIoError::NotFound(file))?;
+                Ok(Metadata::new(if metadata.is_dir() {
+                    bevy::asset::FileType::Directory
+                } else {
+                    bevy::asset::FileType::File
+                }))
+            }
+        }
+    }
+
+    /// Only the base `assets` folder is watched, packs are not hot reloaded
+    fn watch_path_for_changes(
+        &self,
+        to_watch: &Path,
+        to_reload: Option<PathBuf>,
+    ) -> Result<(), AssetIoError> {
+        match self.layer(to_watch) {
+            Layer::Base(path) => self.base.watch_path_for_changes(path, to_reload),
+            Layer::Pack(_) => Ok(()),
+        }
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.base.watch_for_changes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mods::manifest::ModManifest;
+
+    fn example_pack() -> ModAssetIo {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        ModAssetIo {
+            base: Box::new(FileAssetIo::new(root.join("assets"), false)),
+            packs: vec![ModPack {
+                manifest: ModManifest {
+                    id: "example_pack".to_string(),
+                    name: "Example Pack".to_string(),
+                    version: "0.1.0".parse().unwrap(),
+                    description: String::new(),
+                    dependencies: vec![],
+                },
+                folder: root.join("mods/example_pack"),
+            }],
+        }
+    }
+
+    #[test]
+    fn paths_resolve_to_the_topmost_layer() {
+        let io = example_pack();
+        let folder = &io.packs[0].folder;
+        let pack_file = Path::new("data/base.items.ron");
+        let base_file = Path::new("data/base.moves.ron");
+        assert_eq!(io.layer(pack_file), Layer::Pack(folder.join(pack_file)));
+        assert_eq!(io.layer(base_file), Layer::Base(base_file));
+        assert_eq!(
+            io.layer(Path::new("base/data/base.items.ron")),
+            Layer::Base(pack_file)
+        );
+        assert_eq!(
+            io.layer(Path::new("mods/example_pack/data/base.moves.ron")),
+            Layer::Pack(folder.join(base_file))
+        );
+        // unknown packs are just a folder called `mods`
+        let unknown = Path::new("mods/unknown/data/base.items.ron");
+        assert_eq!(io.layer(unknown), Layer::Base(unknown));
+    }
+
+    #[test]
+    fn directories_list_every_layer() {
+        let io = example_pack();
+        let list = |path: &str| {
+            let mut entries: Vec<PathBuf> = io.read_directory(Path::new(path)).unwrap().collect();
+            entries.sort();
+            entries
+        };
+        let merged = list("data");
+        let base = list("base/data");
+        assert_eq!(merged.len(), base.len());
+        assert!(merged.contains(&PathBuf::from("data/base.items.ron")));
+        assert!(base.contains(&PathBuf::from("base/data/base.moves.ron")));
+        assert_eq!(
+            list("mods/example_pack/data"),
+            [
+                PathBuf::from("mods/example_pack/data/base.items.ron"),
+                PathBuf::from("mods/example_pack/data/base.shops.ron"),
+            ]
+        );
+        assert!(io.read_directory(Path::new("nowhere")).is_err());
+    }
+}
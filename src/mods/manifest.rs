@@ -0,0 +1,229 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Every pack has one of these in its folder
+pub const MANIFEST_FILE: &str = "mod.ron";
+
+/// What a content pack says about itself, see `mods/example_pack/mod.ron`
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModManifest {
+    /// Used by other packs to depend on this one and in the mod settings
+    pub id: String,
+    pub name: String,
+    pub version: Version,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+/// A pack that has to be loaded before the one depending on it
+#[derive(Deserialize, Clone, Debug)]
+pub struct Dependency {
+    pub id: String,
+    /// The lowest version that works, later versions work as long as the major version matches
+    pub version: Version,
+}
+
+/// A version like `1.2.0`
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String")]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Whether a pack of this version can stand in for `required`
+    pub fn satisfies(&self, required: &Version) -> bool {
+        self.major == required.major && self >= required
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<u32> = value
+            .split('.')
+            .map(|part| part.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("\"{value}\" is not a version like 1.2.0"))?;
+        match parts[..] {
+            [major, minor, patch] => Ok(Version {
+                major,
+                minor,
+                patch,
+            }),
+            [major, minor] => Ok(Version {
+                major,
+                minor,
+                patch: 0,
+            }),
+            _ => Err(format!("\"{value}\" is not a version like 1.2.0")),
+        }
+    }
+}
+
+impl TryFrom<String> for Version {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// An installed pack
+#[derive(Clone, Debug)]
+pub struct ModPack {
+    pub manifest: ModManifest,
+    pub folder: PathBuf,
+}
+
+/// Reads the manifest of every folder in `mods_folder`
+/// Folders with a broken or missing manifest are returned as problems, keyed by folder name
+pub fn discover(mods_folder: &Path) -> (Vec<ModPack>, BTreeMap<String, String>) {
+    let mut packs = vec![];
+    let mut problems = BTreeMap::new();
+    let Ok(entries) = std::fs::read_dir(mods_folder) else {
+        return (packs, problems);
+    };
+    for folder in entries.flatten().map(|entry| entry.path()) {
+        if !folder.is_dir() {
+            continue;
+        }
+        let name = folder
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+        let manifest = std::fs::read(folder.join(MANIFEST_FILE))
+            .map_err(|error| error.to_string())
+            .and_then(|bytes| {
+                ron::de::from_bytes::<ModManifest>(&bytes).map_err(|error| error.to_string())
+            });
+        match manifest {
+            Ok(manifest) => packs.push(ModPack { manifest, folder }),
+            Err(error) => {
+                problems.insert(name, format!("{MANIFEST_FILE} can't be read: {error}"));
+            }
+        }
+    }
+    packs.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+    (packs, problems)
+}
+
+/// Picks the packs to load from the enabled ids, in that order
+/// Packs that are missing or whose dependencies aren't loaded before them are left out,
+/// the reason is returned keyed by pack id
+pub fn resolve(
+    installed: &[ModPack],
+    enabled: &[String],
+) -> (Vec<ModPack>, BTreeMap<String, String>) {
+    let mut active: Vec<ModPack> = vec![];
+    let mut problems = BTreeMap::new();
+    for id in enabled {
+        let Some(pack) = installed.iter().find(|pack| pack.manifest.id == *id) else {
+            problems.insert(id.clone(), "is not installed".to_string());
+            continue;
+        };
+        let missing: Vec<String> = pack
+            .manifest
+            .dependencies
+            .iter()
+            .filter(|dependency| {
+                !active.iter().any(|loaded| {
+                    loaded.manifest.id == dependency.id
+                        && loaded.manifest.version.satisfies(&dependency.version)
+                })
+            })
+            .map(|dependency| format!("{} {}", dependency.id, dependency.version))
+            .collect();
+        if missing.is_empty() {
+            active.push(pack.clone());
+        } else {
+            problems.insert(
+                id.clone(),
+                format!("needs {} loaded before it", missing.join(", ")),
+            );
+        }
+    }
+    (active, problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(id: &str, version: &str, dependencies: &[(&str, &str)]) -> ModPack {
+        ModPack {
+            manifest: ModManifest {
+                id: id.to_string(),
+                name: id.to_string(),
+                version: version.parse().unwrap(),
+                description: String::new(),
+                dependencies: dependencies
+                    .iter()
+                    .map(|(id, version)| Dependency {
+                        id: id.to_string(),
+                        version: version.parse().unwrap(),
+                    })
+                    .collect(),
+            },
+            folder: PathBuf::new(),
+        }
+    }
+
+    fn ids(packs: &[ModPack]) -> Vec<&str> {
+        packs.iter().map(|pack| pack.manifest.id.as_str()).collect()
+    }
+
+    #[test]
+    fn versions_need_the_same_major_version() {
+        let version: Version = "1.4.2".parse().unwrap();
+        assert!(version.satisfies(&"1.2".parse().unwrap()));
+        assert!(!version.satisfies(&"1.5.0".parse().unwrap()));
+        assert!(!version.satisfies(&"0.9.0".parse().unwrap()));
+        assert!("1.x".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn dependencies_load_first() {
+        let installed = [
+            pack("base_plus", "1.1.0", &[]),
+            pack("addon", "0.1.0", &[("base_plus", "1.0.0")]),
+        ];
+        let enabled = ["base_plus".to_string(), "addon".to_string()];
+        let (active, problems) = resolve(&installed, &enabled);
+        assert_eq!(ids(&active), ["base_plus", "addon"]);
+        assert!(problems.is_empty());
+
+        let (active, problems) = resolve(&installed, &["addon".to_string()]);
+        assert!(active.is_empty());
+        assert!(problems.contains_key("addon"));
+    }
+
+    #[test]
+    fn outdated_dependencies_and_missing_packs_are_left_out() {
+        let installed = [
+            pack("base_plus", "1.0.0", &[]),
+            pack("addon", "0.1.0", &[("base_plus", "1.2.0")]),
+        ];
+        let enabled = [
+            "gone".to_string(),
+            "base_plus".to_string(),
+            "addon".to_string(),
+        ];
+        let (active, problems) = resolve(&installed, &enabled);
+        assert_eq!(ids(&active), ["base_plus"]);
+        assert_eq!(problems.len(), 2);
+    }
+}
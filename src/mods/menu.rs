@@ -0,0 +1,244 @@
+use crate::actions::Actions;
+use crate::loading::FontAssets;
+use crate::mods::{ActiveMods, InstalledMods, ModSettings};
+use crate::ui::focus::CancelButton;
+use crate::ui::{spawn_button, ButtonColors};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+pub struct ModsMenuPlugin;
+
+/// This plugin draws the list of installed content packs on top of the title screen,
+/// where they can be enabled, disabled and moved up in the load order
+/// The menu is only drawn during the State `ModsMenuState::Open`
+impl Plugin for ModsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<ModsMenuState>()
+            .add_system(setup_mods_menu.in_schedule(OnEnter(ModsMenuState::Open)))
+            .add_systems(
+                (click_mods_button, draw_mod_list, close_mods_menu)
+                    .chain()
+                    .in_set(OnUpdate(ModsMenuState::Open)),
+            )
+            .add_system(cleanup_mods_menu.in_schedule(OnExit(ModsMenuState::Open)));
+    }
+}
+
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ModsMenuState {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(Component)]
+struct ModsMenu;
+
+/// Holds a row for every pack, redrawn when the [`ModSettings`] change
+#[derive(Component)]
+struct ModList;
+
+#[derive(Component)]
+enum ModsButton {
+    Toggle(String),
+    LoadEarlier(String),
+    Back,
+}
+
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const PROBLEM_COLOR: Color = Color::rgb(0.9, 0.45, 0.35);
+const SMALL_BUTTON: Size = Size::new(Val::Px(90.0), Val::Px(50.0));
+
+fn setup_mods_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    mut mod_settings: ResMut<ModSettings>,
+) {
+    // draws the list right away
+    mod_settings.set_changed();
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.1, 0.1, 0.1, 0.95).into(),
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(ModsMenu)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Mods",
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 50.0,
+                    color: TEXT_COLOR,
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::vertical(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(ModList);
+            spawn_button(
+                parent,
+                "Back",
+                font_assets.fira_sans.clone(),
+                &button_colors,
+                Size::new(Val::Px(200.0), Val::Px(60.0)),
+            )
+            .insert((ModsButton::Back, CancelButton));
+        });
+}
+
+fn click_mods_button(
+    mut mod_settings: ResMut<ModSettings>,
+    mut next_state: ResMut<NextState<ModsMenuState>>,
+    interaction_query: Query<(&Interaction, &ModsButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            ModsButton::Toggle(id) => mod_settings.toggle(id),
+            ModsButton::LoadEarlier(id) => mod_settings.load_earlier(id),
+            ModsButton::Back => next_state.set(ModsMenuState::Closed),
+        }
+    }
+}
+
+fn draw_mod_list(
+    mut commands: Commands,
+    mod_settings: Res<ModSettings>,
+    installed: Res<InstalledMods>,
+    active: Res<ActiveMods>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    list: Query<Entity, With<ModList>>,
+) {
+    if !mod_settings.is_changed() {
+        return;
+    }
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.fira_sans.clone(),
+                font_size,
+                color,
+            },
+        )
+    };
+    let active_ids: Vec<&str> = active
+        .packs
+        .iter()
+        .map(|pack| pack.manifest.id.as_str())
+        .collect();
+    let needs_restart = mod_settings.enabled != active_ids;
+    for entity in &list {
+        let mut list = commands.entity(entity);
+        list.despawn_descendants();
+        list.with_children(|parent| {
+            if installed.packs.is_empty() {
+                parent.spawn(text(
+                    "No mods installed, drop them into the mods folder".to_string(),
+                    26.0,
+                    TEXT_COLOR,
+                ));
+            }
+            for pack in &installed.packs {
+                let id = &pack.manifest.id;
+                let position = mod_settings
+                    .enabled
+                    .iter()
+                    .position(|enabled| enabled == id);
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        let toggle = if position.is_some() { "On" } else { "Off" };
+                        spawn_button(
+                            row,
+                            toggle,
+                            font_assets.fira_sans.clone(),
+                            &button_colors,
+                            SMALL_BUTTON,
+                        )
+                        .insert(ModsButton::Toggle(id.clone()));
+                        if position.is_some_and(|position| position > 0) {
+                            spawn_button(
+                                row,
+                                "Up",
+                                font_assets.fira_sans.clone(),
+                                &button_colors,
+                                SMALL_BUTTON,
+                            )
+                            .insert(ModsButton::LoadEarlier(id.clone()));
+                        }
+                        let mut label = format!("{} {}", pack.manifest.name, pack.manifest.version);
+                        if let Some(position) = position {
+                            label = format!("{}. {label}", position + 1);
+                        }
+                        row.spawn(text(label, 28.0, TEXT_COLOR)).insert(Style {
+                            margin: UiRect::left(Val::Px(10.0)),
+                            ..default()
+                        });
+                    });
+                if !pack.manifest.description.is_empty() {
+                    parent.spawn(text(pack.manifest.description.clone(), 20.0, TEXT_COLOR));
+                }
+                if let Some(problem) = installed.problems.get(id) {
+                    parent.spawn(text(format!("This mod {problem}"), 20.0, PROBLEM_COLOR));
+                }
+            }
+            // folders without a readable manifest
+            for (folder, problem) in &installed.problems {
+                if !installed
+                    .packs
+                    .iter()
+                    .any(|pack| pack.manifest.id == *folder)
+                {
+                    parent.spawn(text(format!("{folder}: {problem}"), 20.0, PROBLEM_COLOR));
+                }
+            }
+            if needs_restart {
+                parent.spawn(text(
+                    "Restart the game to apply the changes".to_string(),
+                    24.0,
+                    TEXT_COLOR,
+                ));
+            }
+        });
+    }
+}
+
+/// The menu key doubles as "back" while the mods menu is open
+fn close_mods_menu(actions: Res<Actions>, mut next_state: ResMut<NextState<ModsMenuState>>) {
+    if actions.toggle_pause {
+        next_state.set(ModsMenuState::Closed);
+    }
+}
+
+fn cleanup_mods_menu(mut commands: Commands, mods_menu: Query<Entity, With<ModsMenu>>) {
+    for entity in &mods_menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,265 @@
+use crate::dialogue::StartDialogue;
+use crate::fight_stage::PendingBattle;
+use crate::flags::Flags;
+use crate::loading::{MapAssets, TextureAssets};
+use crate::overworld::map::{Map, MapLoader};
+use crate::overworld::npc::{read_sign, spot_player, talk_to_npc, DefeatedTrainers};
+use crate::party::PartyMember;
+use crate::player::{move_player, spawn_player, Player};
+use crate::quests::Quests;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub mod map;
+pub mod npc;
+
+/// Npcs are drawn as colored squares for now
+const NPC_COLOR: Color = Color::rgb(0.8, 0.35, 0.3);
+
+pub struct OverworldPlugin;
+
+/// This plugin draws the overworld map around the player, follows the player with the camera,
+/// lets npcs talk to the player and moves the player between maps or into fights
+/// The overworld only exists during the State `GameState::Playing`
+impl Plugin for OverworldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Map>()
+            .init_asset_loader::<MapLoader>()
+            .add_event::<EnteredTile>()
+            .init_resource::<DefeatedTrainers>()
+            .add_systems(
+                (
+                    spawn_map.before(move_player),
+                    track_player_tile.after(move_player),
+                    take_warp.after(track_player_tile),
+                    start_encounter.after(track_player_tile),
+                    spot_player.after(track_player_tile),
+                    talk_to_npc,
+                    read_sign,
+                    follow_player.after(move_player),
+                )
+                    .in_set(OnUpdate(GameState::Playing)),
+            )
+            .add_system(leave_overworld.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+/// Where the player is in the overworld
+///
+/// Outlives the overworld entities, so the player ends up in the same spot after a fight.
+#[derive(Resource)]
+pub struct PlayerLocation {
+    pub map: Handle<Map>,
+    /// `None` until the map is loaded and the player is placed on its spawn tile
+    pub tile: Option<IVec2>,
+}
+
+impl PlayerLocation {
+    pub fn new_game(map_assets: &MapAssets) -> Self {
+        PlayerLocation {
+            map: map_assets.town.clone(),
+            tile: None,
+        }
+    }
+}
+
+/// Sent whenever the player walks onto another tile
+pub struct EnteredTile {
+    pub tile: IVec2,
+}
+
+/// Everything belonging to the current map, including the player
+#[derive(Component)]
+pub struct OnMap;
+
+fn spawn_map(
+    mut commands: Commands,
+    mut location: ResMut<PlayerLocation>,
+    maps: Res<Assets<Map>>,
+    textures: Res<TextureAssets>,
+    spawned: Query<(), With<OnMap>>,
+) {
+    if !spawned.is_empty() {
+        return;
+    }
+    // maps reached through a warp might still be loading
+    let Some(map) = maps.get(&location.map) else {
+        return;
+    };
+    info!("Entering {}", map.name);
+    commands
+        .spawn(SpatialBundle::default())
+        .insert(OnMap)
+        .with_children(|parent| {
+            for (tile, kind) in map.tiles() {
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: kind.color(),
+                        custom_size: Some(Vec2::splat(map.tile_size)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(map.tile_to_world(tile).extend(0.)),
+                    ..default()
+                });
+            }
+            for npc in &map.npcs {
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: NPC_COLOR,
+                        custom_size: Some(Vec2::splat(map.tile_size * 0.8)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        map.tile_to_world(npc.tile.into()).extend(0.5),
+                    ),
+                    ..default()
+                });
+            }
+            for prop in &map.props {
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: prop.kind.color(),
+                        custom_size: Some(prop.kind.size().as_vec2() * map.tile_size),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(map.prop_to_world(prop).extend(0.5)),
+                    ..default()
+                });
+            }
+        });
+    let tile = *location.tile.get_or_insert(map.spawn);
+    spawn_player(
+        &mut commands,
+        &textures,
+        map.tile_to_world(tile),
+        map.tile_size,
+    )
+    .insert(OnMap);
+}
+
+fn track_player_tile(
+    mut location: ResMut<PlayerLocation>,
+    mut entered_tile: EventWriter<EnteredTile>,
+    maps: Res<Assets<Map>>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let (Ok(transform), Some(map)) = (player_query.get_single(), maps.get(&location.map)) else {
+        return;
+    };
+    let tile = map.world_to_tile(transform.translation.truncate());
+    if location.tile == Some(tile) {
+        return;
+    }
+    location.tile = Some(tile);
+    entered_tile.send(EnteredTile { tile });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn take_warp(
+    mut commands: Commands,
+    mut location: ResMut<PlayerLocation>,
+    mut flags: ResMut<Flags>,
+    quests: Res<Quests>,
+    mut entered_tile: EventReader<EnteredTile>,
+    mut start_dialogue: EventWriter<StartDialogue>,
+    asset_server: Res<AssetServer>,
+    maps: Res<Assets<Map>>,
+    on_map: Query<Entity, With<OnMap>>,
+) {
+    let Some(map) = maps.get(&location.map) else {
+        return;
+    };
+    let Some(warp) = entered_tile
+        .iter()
+        .find_map(|entered| map.warp_at(entered.tile))
+        .cloned()
+    else {
+        return;
+    };
+    let unlocked = warp
+        .condition
+        .iter()
+        .all(|condition| condition.is_met(&flags, &quests));
+    if !unlocked {
+        if let Some(locked) = &warp.locked {
+            start_dialogue.send(StartDialogue::script(locked));
+        }
+        return;
+    }
+    for change in &warp.on_enter {
+        flags.apply(change);
+    }
+    info!("Warping to {} at {:?}", warp.map, warp.to);
+    location.map = asset_server.load(warp.map.as_str());
+    location.tile = Some(warp.to.into());
+    for entity in &on_map {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Every step into an encounter zone might start a fight with a wild creature
+fn start_encounter(
+    mut commands: Commands,
+    mut entered_tile: EventReader<EnteredTile>,
+    mut next_state: ResMut<NextState<GameState>>,
+    location: Res<PlayerLocation>,
+    flags: Res<Flags>,
+    quests: Res<Quests>,
+    maps: Res<Assets<Map>>,
+) {
+    let Some(map) = maps.get(&location.map) else {
+        return;
+    };
+    for entered in entered_tile.iter() {
+        let Some(zone) = map.encounter_zones_at(entered.tile).find(|zone| {
+            zone.condition
+                .iter()
+                .all(|condition| condition.is_met(&flags, &quests))
+        }) else {
+            continue;
+        };
+        if rand::random::<f32>() >= zone.chance {
+            continue;
+        }
+        let Some((species, level)) = zone.pick(rand::random(), rand::random()) else {
+            warn!("Encounter zone at {:?} has no species", zone.area);
+            continue;
+        };
+        info!("A wild {species} (level {level}) appeared");
+        commands.insert_resource(PendingBattle {
+            opponents: vec![PartyMember::wild(species, level)],
+            trainer: None,
+            on_win: zone.on_win.clone(),
+        });
+        next_state.set(GameState::Fight);
+        return;
+    }
+}
+
+fn follow_player(
+    player_query: Query<&Transform, With<Player>>,
+    mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<Player>)>,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    for mut camera in &mut camera_query {
+        camera.translation.x = player.translation.x;
+        camera.translation.y = player.translation.y;
+    }
+}
+
+/// The fight stage and menus expect the camera at the origin
+fn leave_overworld(
+    mut commands: Commands,
+    on_map: Query<Entity, With<OnMap>>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    for entity in &on_map {
+        commands.entity(entity).despawn_recursive();
+    }
+    for mut camera in &mut camera_query {
+        camera.translation.x = 0.;
+        camera.translation.y = 0.;
+    }
+}
@@ -0,0 +1,430 @@
+use crate::flags::{Condition, FlagChange};
+use crate::party::PartyMember;
+use bevy::asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+/// Boxes may overlap solid tiles by this much, so boxes flush against a wall don't collide with it
+const CONTACT_SLACK: f32 = 0.01;
+
+/// A tile based overworld map, loaded from a `.map.ron` file
+#[derive(TypeUuid, Debug)]
+#[uuid = "d5bf1058-7834-4cae-94a0-0472416afafa"]
+pub struct Map {
+    pub name: String,
+    pub tile_size: f32,
+    pub width: i32,
+    pub height: i32,
+    /// Row by row, starting at the top left
+    tiles: Vec<TileKind>,
+    /// Where a new game starts on this map
+    pub spawn: IVec2,
+    pub warps: Vec<Warp>,
+    pub props: Vec<Prop>,
+    pub encounters: Vec<EncounterZone>,
+    pub npcs: Vec<Npc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileKind {
+    Floor,
+    Wall,
+    Water,
+    /// Tall piles of garbage, creatures like to hide in them
+    Garbage,
+    Door,
+}
+
+/// Stepping on `tile` moves the player to `to` on another map
+#[derive(Deserialize, Clone, Debug)]
+pub struct Warp {
+    pub tile: (i32, i32),
+    /// Asset path of the target map
+    pub map: String,
+    pub to: (i32, i32),
+    /// The warp only works while this is met
+    #[serde(default)]
+    pub condition: Option<Condition>,
+    /// Dialogue node run when the condition isn't met, like a locked door talking back
+    #[serde(default)]
+    pub locked: Option<String>,
+    /// Applied to the story flags when the player takes the warp
+    #[serde(default)]
+    pub on_enter: Vec<FlagChange>,
+}
+
+/// Something standing on the map, blocking the tiles it covers
+#[derive(Deserialize, Clone, Debug)]
+pub struct Prop {
+    pub kind: PropKind,
+    /// Top left tile covered by the prop
+    pub tile: (i32, i32),
+    /// Dialogue node run when the player reads the prop, like the text on a sign
+    #[serde(default)]
+    pub script: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropKind {
+    Dumpster,
+    TrashCan,
+    Crate,
+    Sign,
+}
+
+/// An area where wild creatures may jump at the player
+#[derive(Deserialize, Clone, Debug)]
+pub struct EncounterZone {
+    /// Top left and bottom right tile, both included
+    pub area: ((i32, i32), (i32, i32)),
+    /// Chance to start a fight with every tile the player walks onto
+    pub chance: f32,
+    /// Lowest and highest level (both included) of the creatures met here
+    pub levels: (u32, u32),
+    /// Species ids with their relative weights
+    pub species: Vec<(String, u32)>,
+    /// Creatures only show up while this is met
+    #[serde(default)]
+    pub condition: Option<Condition>,
+    /// Applied to the story flags whenever the player beats a creature from here
+    #[serde(default)]
+    pub on_win: Vec<FlagChange>,
+}
+
+impl EncounterZone {
+    pub fn contains(&self, tile: IVec2) -> bool {
+        let (top_left, bottom_right) = self.area;
+        tile.cmpge(top_left.into()).all() && tile.cmple(bottom_right.into()).all()
+    }
+
+    /// Picks a species and level, `roll` and `level_roll` being random numbers in `0..1`
+    pub fn pick(&self, roll: f32, level_roll: f32) -> Option<(&str, u32)> {
+        let total: u32 = self.species.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut remaining = (roll * total as f32) as u32;
+        let (species, _) = self.species.iter().find(|(_, weight)| {
+            if remaining < *weight {
+                return true;
+            }
+            remaining -= weight;
+            false
+        })?;
+        let (lowest, highest) = self.levels;
+        let level = lowest + (level_roll * (highest.saturating_sub(lowest) + 1) as f32) as u32;
+        Some((species, level.min(highest)))
+    }
+}
+
+/// Someone standing on the map, to talk to or to fight
+#[derive(Deserialize, Clone, Debug)]
+pub struct Npc {
+    /// Unique across all maps, used to remember defeated trainers
+    pub id: String,
+    pub name: String,
+    pub tile: (i32, i32),
+    #[serde(default)]
+    pub facing: Facing,
+    /// How many tiles ahead a trainer spots the player
+    #[serde(default)]
+    pub sight: i32,
+    /// Said when talked to, or when a trainer spots the player
+    #[serde(default)]
+    pub intro: Vec<String>,
+    /// Dialogue node to run instead of the `intro` lines
+    #[serde(default)]
+    pub script: Option<String>,
+    /// Said by a trainer after losing the fight, and whenever talked to afterwards
+    #[serde(default)]
+    pub defeated: Vec<String>,
+    /// Npcs with a party are trainers and challenge the player to a fight
+    #[serde(default)]
+    pub party: Vec<PartyMember>,
+    /// Applied to the story flags once the player beats the trainer
+    #[serde(default)]
+    pub on_defeat: Vec<FlagChange>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Facing {
+    Up,
+    #[default]
+    Down,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// One tile in this direction, in tile coordinates
+    pub fn offset(&self) -> IVec2 {
+        match self {
+            Facing::Up => IVec2::NEG_Y,
+            Facing::Down => IVec2::Y,
+            Facing::Left => IVec2::NEG_X,
+            Facing::Right => IVec2::X,
+        }
+    }
+}
+
+impl Npc {
+    pub fn is_trainer(&self) -> bool {
+        !self.party.is_empty()
+    }
+
+    /// Whether `target` is in sight, looking straight ahead until a solid tile blocks the view
+    pub fn can_see(&self, map: &Map, target: IVec2) -> bool {
+        let tile = IVec2::from(self.tile);
+        (1..=self.sight)
+            .map(|distance| tile + self.facing.offset() * distance)
+            .take_while(|tile| *tile == target || !map.is_solid(*tile))
+            .any(|tile| tile == target)
+    }
+}
+
+/// The map as it is written in the file
+#[derive(Deserialize)]
+struct MapFile {
+    name: String,
+    tile_size: f32,
+    /// One string per row, one character per tile (see [`TileKind::from_char`])
+    tiles: Vec<String>,
+    spawn: (i32, i32),
+    #[serde(default)]
+    warps: Vec<Warp>,
+    #[serde(default)]
+    props: Vec<Prop>,
+    #[serde(default)]
+    encounters: Vec<EncounterZone>,
+    #[serde(default)]
+    npcs: Vec<Npc>,
+}
+
+impl TileKind {
+    fn from_char(character: char) -> Option<TileKind> {
+        match character {
+            '.' => Some(TileKind::Floor),
+            '#' => Some(TileKind::Wall),
+            '~' => Some(TileKind::Water),
+            '%' => Some(TileKind::Garbage),
+            'D' => Some(TileKind::Door),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            TileKind::Floor => Color::rgb(0.45, 0.42, 0.36),
+            TileKind::Wall => Color::rgb(0.2, 0.2, 0.22),
+            TileKind::Water => Color::rgb(0.18, 0.3, 0.42),
+            TileKind::Garbage => Color::rgb(0.35, 0.45, 0.2),
+            TileKind::Door => Color::rgb(0.5, 0.3, 0.15),
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        matches!(self, TileKind::Wall | TileKind::Water)
+    }
+}
+
+impl PropKind {
+    /// Size in tiles
+    pub fn size(&self) -> IVec2 {
+        match self {
+            PropKind::Dumpster => IVec2::new(2, 1),
+            PropKind::TrashCan | PropKind::Crate | PropKind::Sign => IVec2::ONE,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            PropKind::Dumpster => Color::rgb(0.15, 0.35, 0.2),
+            PropKind::TrashCan => Color::rgb(0.5, 0.5, 0.55),
+            PropKind::Crate => Color::rgb(0.55, 0.4, 0.2),
+            PropKind::Sign => Color::rgb(0.75, 0.6, 0.3),
+        }
+    }
+}
+
+impl Prop {
+    pub fn covers(&self, tile: IVec2) -> bool {
+        let top_left = IVec2::from(self.tile);
+        let bottom_right = top_left + self.kind.size();
+        tile.cmpge(top_left).all() && tile.cmplt(bottom_right).all()
+    }
+}
+
+impl Map {
+    pub fn from_ron(bytes: &[u8]) -> Result<Map, Error> {
+        Map::from_file(ron::de::from_bytes(bytes)?)
+    }
+
+    /// Tiles outside of the map count as walls
+    pub fn tile(&self, tile: IVec2) -> TileKind {
+        if tile.x < 0 || tile.y < 0 || tile.x >= self.width || tile.y >= self.height {
+            return TileKind::Wall;
+        }
+        self.tiles[(tile.y * self.width + tile.x) as usize]
+    }
+
+    /// Whether the player can't walk onto the tile, because of the tile itself or something on it
+    pub fn is_solid(&self, tile: IVec2) -> bool {
+        self.tile(tile).is_solid()
+            || self.props.iter().any(|prop| prop.covers(tile))
+            || self.npcs.iter().any(|npc| IVec2::from(npc.tile) == tile)
+    }
+
+    /// Whether a box at `center` (in world space) overlaps any solid tile
+    ///
+    /// Boxes just touching a solid tile don't count.
+    pub fn collides(&self, center: Vec2, half_size: Vec2) -> bool {
+        let first = self.world_to_tile(center - half_size + CONTACT_SLACK);
+        let last = self.world_to_tile(center + half_size - CONTACT_SLACK);
+        // world_to_tile flips y, so the box' bottom ends up in the last row
+        (first.x..=last.x).any(|x| (last.y..=first.y).any(|y| self.is_solid(IVec2::new(x, y))))
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (IVec2, TileKind)> + '_ {
+        self.tiles.iter().enumerate().map(|(index, kind)| {
+            let index = index as i32;
+            (IVec2::new(index % self.width, index / self.width), *kind)
+        })
+    }
+
+    /// Center of all the tiles covered by the prop in world space
+    pub fn prop_to_world(&self, prop: &Prop) -> Vec2 {
+        let size = (prop.kind.size() - IVec2::ONE).as_vec2();
+        self.tile_to_world(prop.tile.into()) + Vec2::new(size.x, -size.y) * self.tile_size / 2.
+    }
+
+    /// Center of the tile in world space
+    ///
+    /// Rows count downwards in the file, but world space y points up, so rows go to negative y.
+    pub fn tile_to_world(&self, tile: IVec2) -> Vec2 {
+        Vec2::new(tile.x as f32, -tile.y as f32) * self.tile_size
+    }
+
+    pub fn world_to_tile(&self, position: Vec2) -> IVec2 {
+        let tile = (position / self.tile_size).round();
+        IVec2::new(tile.x as i32, -tile.y as i32)
+    }
+
+    pub fn warp_at(&self, tile: IVec2) -> Option<&Warp> {
+        self.warps
+            .iter()
+            .find(|warp| IVec2::from(warp.tile) == tile)
+    }
+
+    pub fn encounter_zones_at(&self, tile: IVec2) -> impl Iterator<Item = &EncounterZone> {
+        self.encounters
+            .iter()
+            .filter(move |zone| zone.contains(tile))
+    }
+
+    fn from_file(file: MapFile) -> Result<Map, Error> {
+        let height = file.tiles.len() as i32;
+        let width = file
+            .tiles
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0) as i32;
+        let mut tiles = Vec::with_capacity((width * height) as usize);
+        for (y, row) in file.tiles.iter().enumerate() {
+            if row.chars().count() as i32 != width {
+                return Err(Error::msg(format!(
+                    "row {y} of map \"{}\" is not {width} tiles wide",
+                    file.name
+                )));
+            }
+            for (x, character) in row.chars().enumerate() {
+                let kind = TileKind::from_char(character).ok_or_else(|| {
+                    Error::msg(format!("unknown tile '{character}' at ({x}, {y})"))
+                })?;
+                tiles.push(kind);
+            }
+        }
+        Ok(Map {
+            name: file.name,
+            tile_size: file.tile_size,
+            width,
+            height,
+            tiles,
+            spawn: file.spawn.into(),
+            warps: file.warps,
+            props: file.props,
+            encounters: file.encounters,
+            npcs: file.npcs,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct MapLoader;
+
+impl AssetLoader for MapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            load_context.set_default_asset(LoadedAsset::new(Map::from_ron(bytes)?));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(species: &[(&str, u32)], levels: (u32, u32)) -> EncounterZone {
+        EncounterZone {
+            area: ((0, 0), (0, 0)),
+            chance: 1.,
+            levels,
+            species: species
+                .iter()
+                .map(|(id, weight)| (id.to_string(), *weight))
+                .collect(),
+            condition: None,
+            on_win: vec![],
+        }
+    }
+
+    const JUST_BELOW_ONE: f32 = 1. - f32::EPSILON;
+
+    #[test]
+    fn picking_follows_the_weights() {
+        let zone = zone(&[("a", 1), ("b", 0), ("c", 3)], (2, 4));
+        let cases = [
+            (0., 0., Some(("a", 2))),
+            (0.24, 0.5, Some(("a", 3))),
+            (0.25, 0.5, Some(("c", 3))),
+            (JUST_BELOW_ONE, JUST_BELOW_ONE, Some(("c", 4))),
+        ];
+        for (roll, level_roll, expected) in cases {
+            assert_eq!(zone.pick(roll, level_roll), expected, "roll {roll}");
+        }
+    }
+
+    #[test]
+    fn zero_weights_are_never_picked() {
+        let zone = zone(&[("never", 0), ("always", 2), ("also never", 0)], (5, 5));
+        for roll in [0., 0.5, JUST_BELOW_ONE] {
+            assert_eq!(zone.pick(roll, roll), Some(("always", 5)), "roll {roll}");
+        }
+    }
+
+    #[test]
+    fn empty_zones_have_nothing_to_pick() {
+        assert_eq!(zone(&[], (1, 3)).pick(0.5, 0.5), None);
+        assert_eq!(zone(&[("a", 0), ("b", 0)], (1, 3)).pick(0.5, 0.5), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::actions::Actions;
+use crate::dialogue::{DialogueOutcome, StartDialogue};
+use crate::fight_stage::{PendingBattle, Trainer};
+use crate::overworld::map::{Map, Npc};
+use crate::overworld::PlayerLocation;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Ids of all trainers the player has beaten, they don't challenge the player again
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+pub struct DefeatedTrainers(pub BTreeSet<String>);
+
+/// Trainers challenge the player as soon as the player walks into their line of sight
+pub fn spot_player(
+    location: Res<PlayerLocation>,
+    maps: Res<Assets<Map>>,
+    defeated: Res<DefeatedTrainers>,
+    mut start_dialogue: EventWriter<StartDialogue>,
+) {
+    let (Some(map), Some(player_tile)) = (maps.get(&location.map), location.tile) else {
+        return;
+    };
+    let spotted_by = map.npcs.iter().find(|npc| {
+        npc.is_trainer() && !defeated.0.contains(&npc.id) && npc.can_see(map, player_tile)
+    });
+    if let Some(trainer) = spotted_by {
+        start_dialogue.send(challenge(trainer));
+    }
+}
+
+/// Confirm next to an npc starts a conversation
+pub fn talk_to_npc(
+    actions: Res<Actions>,
+    location: Res<PlayerLocation>,
+    maps: Res<Assets<Map>>,
+    defeated: Res<DefeatedTrainers>,
+    mut start_dialogue: EventWriter<StartDialogue>,
+) {
+    if !actions.confirm {
+        return;
+    }
+    let (Some(map), Some(player_tile)) = (maps.get(&location.map), location.tile) else {
+        return;
+    };
+    let Some(npc) = map.npcs.iter().find(|npc| is_next_to(npc, player_tile)) else {
+        return;
+    };
+    let beaten = defeated.0.contains(&npc.id);
+    if npc.is_trainer() && !beaten {
+        start_dialogue.send(challenge(npc));
+        return;
+    }
+    if beaten && !npc.defeated.is_empty() {
+        start_dialogue.send(StartDialogue::lines(Some(&npc.name), npc.defeated.clone()));
+        return;
+    }
+    start_dialogue.send(introduce(npc));
+}
+
+/// Reading a sign or any other prop with a script next to the player
+pub fn read_sign(
+    actions: Res<Actions>,
+    location: Res<PlayerLocation>,
+    maps: Res<Assets<Map>>,
+    mut start_dialogue: EventWriter<StartDialogue>,
+) {
+    if !actions.confirm {
+        return;
+    }
+    let (Some(map), Some(player_tile)) = (maps.get(&location.map), location.tile) else {
+        return;
+    };
+    let script = map.props.iter().find_map(|prop| {
+        let next_to = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .into_iter()
+            .any(|offset| prop.covers(player_tile + offset));
+        prop.script.as_ref().filter(|_| next_to)
+    });
+    if let Some(script) = script {
+        start_dialogue.send(StartDialogue::script(script));
+    }
+}
+
+/// The npc's script if it has one, its intro lines otherwise
+fn introduce(npc: &Npc) -> StartDialogue {
+    match &npc.script {
+        Some(script) => StartDialogue::script(script),
+        None => StartDialogue::lines(Some(&npc.name), npc.intro.clone()),
+    }
+}
+
+fn challenge(trainer: &Npc) -> StartDialogue {
+    introduce(trainer).then(DialogueOutcome::Fight(PendingBattle {
+        opponents: trainer.party.clone(),
+        trainer: Some(Trainer {
+            id: trainer.id.clone(),
+            name: trainer.name.clone(),
+            defeated: trainer.defeated.clone(),
+        }),
+        on_win: trainer.on_defeat.clone(),
+    }))
+}
+
+fn is_next_to(npc: &Npc, tile: IVec2) -> bool {
+    let offset = (IVec2::from(npc.tile) - tile).abs();
+    offset.x + offset.y == 1
+}
@@ -0,0 +1,100 @@
+use crate::creature::species::SpeciesTable;
+use crate::creature::Creature;
+use crate::inventory::items::ItemTable;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A creature outside of a fight, like the player's party or a trainer's team in map files
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PartyMember {
+    pub species: String,
+    pub level: u32,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    /// Move ids, `None` for the species' default moves
+    #[serde(default)]
+    pub moves: Option<Vec<String>>,
+    /// `None` for full health
+    #[serde(default)]
+    pub health: Option<u32>,
+    /// Item id of the item the creature holds
+    #[serde(default)]
+    pub held_item: Option<String>,
+}
+
+/// The creatures travelling with the player
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerParty {
+    pub members: Vec<PartyMember>,
+}
+
+impl PartyMember {
+    pub fn wild(species: &str, level: u32) -> Self {
+        PartyMember {
+            species: species.to_string(),
+            level,
+            nickname: None,
+            moves: None,
+            health: None,
+            held_item: None,
+        }
+    }
+
+    pub fn has_fainted(&self) -> bool {
+        self.health == Some(0)
+    }
+
+    /// Creates the creature for a fight, `None` if the species doesn't exist
+    pub fn to_creature(&self, species_table: &SpeciesTable, items: &ItemTable) -> Option<Creature> {
+        let species = species_table.get(&self.species)?;
+        let mut creature = Creature::new(species, self.level);
+        if let Some(nickname) = &self.nickname {
+            creature = creature.with_nickname(nickname.clone());
+        }
+        if let Some(moves) = &self.moves {
+            creature = creature.with_moves(moves.clone());
+        }
+        if let Some((id, item)) = self
+            .held_item
+            .as_ref()
+            .and_then(|id| Some((id, items.get(id)?)))
+        {
+            creature = creature.with_held_item(id, item);
+        }
+        if let Some(health) = self.health {
+            creature = creature.with_health(health);
+        }
+        Some(creature)
+    }
+}
+
+/// Caught creatures are turned away once the party is this big
+pub const MAX_PARTY_SIZE: usize = 6;
+
+impl Default for PlayerParty {
+    fn default() -> Self {
+        PlayerParty {
+            members: vec![PartyMember {
+                nickname: Some("Jeff".to_string()),
+                ..PartyMember::wild("gargoylemon", 5)
+            }],
+        }
+    }
+}
+
+impl PlayerParty {
+    /// Index of the first member that can still fight
+    pub fn first_able(&self) -> Option<usize> {
+        self.members.iter().position(|member| !member.has_fainted())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= MAX_PARTY_SIZE
+    }
+
+    pub fn heal(&mut self) {
+        for member in &mut self.members {
+            member.health = None;
+        }
+    }
+}
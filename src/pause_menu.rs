@@ -0,0 +1,156 @@
+use crate::actions::Actions;
+use crate::loading::FontAssets;
+use crate::save::{Progress, SaveGame};
+use crate::settings::menu::SettingsMenuState;
+use crate::ui::focus::CancelButton;
+use crate::ui::{spawn_button, ButtonColors, Disabled};
+use crate::{GameState, PauseState};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+pub struct PausePlugin;
+
+/// This plugin freezes the game while the pause menu is open
+/// The menu can be opened during `GameState::Playing` and `GameState::Fight`
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_set(OnUpdate(GameState::Playing).run_if(in_state(PauseState::Running)))
+            .configure_set(OnUpdate(GameState::Fight).run_if(in_state(PauseState::Running)))
+            .add_system(
+                toggle_pause
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Fight)))
+                    .run_if(in_state(SettingsMenuState::Closed)),
+            )
+            .add_system(click_pause_menu_button.run_if(in_state(PauseState::Paused)))
+            .add_system(setup_pause_menu.in_schedule(OnEnter(PauseState::Paused)))
+            .add_system(cleanup_pause_menu.in_schedule(OnExit(PauseState::Paused)))
+            .add_system(resume.in_schedule(OnExit(GameState::Playing)))
+            .add_system(resume.in_schedule(OnExit(GameState::Fight)));
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+enum PauseMenuButton {
+    Resume,
+    Settings,
+    Save,
+    QuitToTitle,
+}
+
+fn toggle_pause(
+    actions: Res<Actions>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if !actions.toggle_pause {
+        return;
+    }
+    match pause_state.0 {
+        PauseState::Running => next_pause_state.set(PauseState::Paused),
+        PauseState::Paused => next_pause_state.set(PauseState::Running),
+    }
+}
+
+fn resume(mut next_pause_state: ResMut<NextState<PauseState>>) {
+    next_pause_state.set(PauseState::Running);
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    game_state: Res<State<GameState>>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    time.pause();
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            // Keep clicks from reaching the frozen fight buttons underneath
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(PauseMenu)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 60.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ));
+            for (label, button) in [
+                ("Resume", PauseMenuButton::Resume),
+                ("Settings", PauseMenuButton::Settings),
+                ("Save", PauseMenuButton::Save),
+                ("Quit to title", PauseMenuButton::QuitToTitle),
+            ] {
+                let mut button_commands = spawn_button(
+                    parent,
+                    label,
+                    font_assets.fira_sans.clone(),
+                    &button_colors,
+                    Size::new(Val::Px(300.0), Val::Px(60.0)),
+                );
+                button_commands.insert(button);
+                if matches!(button, PauseMenuButton::Resume) {
+                    button_commands.insert(CancelButton);
+                }
+                // saving mid fight would lose the fight
+                if matches!(button, PauseMenuButton::Save) && game_state.0 != GameState::Playing {
+                    button_commands.insert(Disabled);
+                }
+            }
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn click_pause_menu_button(
+    progress: Progress,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut next_settings_state: ResMut<NextState<SettingsMenuState>>,
+    interaction_query: Query<
+        (&Interaction, &PauseMenuButton),
+        (Changed<Interaction>, Without<Disabled>),
+    >,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            PauseMenuButton::Resume => next_pause_state.set(PauseState::Running),
+            PauseMenuButton::Settings => next_settings_state.set(SettingsMenuState::Open),
+            PauseMenuButton::QuitToTitle => next_game_state.set(GameState::Title),
+            PauseMenuButton::Save => {
+                SaveGame::store(&progress);
+                next_pause_state.set(PauseState::Running);
+            }
+        }
+    }
+}
+
+fn cleanup_pause_menu(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    pause_menu: Query<Entity, With<PauseMenu>>,
+) {
+    time.unpause();
+    for entity in &pause_menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Name of the folder our files live in, inside the platform's config directory
+const APP_DIRECTORY: &str = "garbagemon";
+
+#[derive(Debug)]
+pub enum PersistenceError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceError::Io(error) => write!(f, "{error}"),
+            PersistenceError::Parse(error) => write!(f, "{error}"),
+            PersistenceError::Serialize(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+/// The directory settings and save games are stored in, if the platform has one
+///
+/// The web build has no file system to write to, so nothing is persisted there.
+pub fn config_directory() -> Option<PathBuf> {
+    if cfg!(target_arch = "wasm32") {
+        return None;
+    }
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join(APP_DIRECTORY))
+}
+
+/// Reads `file_name` from the config directory
+///
+/// Returns `Ok(None)` if the file does not exist (yet).
+pub fn load<T: DeserializeOwned>(file_name: &str) -> Result<Option<T>, PersistenceError> {
+    let Some(path) = config_directory().map(|directory| directory.join(file_name)) else {
+        return Ok(None);
+    };
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(PersistenceError::Io(error)),
+    };
+    ron::from_str(&contents)
+        .map(Some)
+        .map_err(PersistenceError::Parse)
+}
+
+/// Like [`load`], but falls back to `T::default()` if the file is missing or broken
+pub fn load_or_default<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    match load(file_name) {
+        Ok(Some(value)) => value,
+        Ok(None) => T::default(),
+        Err(error) => {
+            warn!("Failed to read {file_name}, falling back to defaults: {error}");
+            T::default()
+        }
+    }
+}
+
+/// Writes `value` to `file_name` in the config directory, creating the directory if needed
+pub fn store<T: Serialize>(file_name: &str, value: &T) -> Result<(), PersistenceError> {
+    let Some(directory) = config_directory() else {
+        return Ok(());
+    };
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(PersistenceError::Serialize)?;
+    std::fs::create_dir_all(&directory).map_err(PersistenceError::Io)?;
+    std::fs::write(directory.join(file_name), contents).map_err(PersistenceError::Io)
+}
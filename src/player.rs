@@ -1,46 +1,308 @@
 use crate::actions::Actions;
+use crate::audio::spatial::SpatialListener;
 use crate::loading::TextureAssets;
+use crate::overworld::map::Map;
+use crate::overworld::PlayerLocation;
+use crate::settings::{MovementMode, Settings};
+use crate::GameState;
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 
+/// Walking speed in pixels per second
+const PLAYER_SPEED: f32 = 150.;
+/// Size of the player's collision box relative to a tile
+///
+/// A bit smaller than a tile, so free movement fits through one tile wide gaps.
+const PLAYER_HITBOX: f32 = 0.8;
+
 pub struct PlayerPlugin;
 
 #[derive(Component)]
 pub struct Player;
 
+/// The tile the player is walking to in [`MovementMode::Grid`]
+#[derive(Component, Default)]
+pub struct GridStep {
+    target: Option<Vec2>,
+}
+
 /// This plugin handles player related stuff like movement
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for PlayerPlugin {
-    fn build(&self, _app: &mut App) {
-        // app.add_system(spawn_player.in_schedule(OnEnter(GameState::Playing)))
-        //     .add_system(move_player.in_set(OnUpdate(GameState::Playing)));
+    fn build(&self, app: &mut App) {
+        app.add_system(move_player.in_set(OnUpdate(GameState::Playing)));
     }
 }
 
-fn _spawn_player(_commands: Commands, _textures: Res<TextureAssets>) {
-    // commands
-    //     .spawn(SpriteBundle {
-    //         texture: textures.texture_bevy.clone(),
-    //         transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
-    //         ..Default::default()
-    //     })
-    //     .insert(Player);
+/// The player is spawned by the overworld together with the map it is standing on
+pub fn spawn_player<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    textures: &TextureAssets,
+    position: Vec2,
+    size: f32,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut player = commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            custom_size: Some(Vec2::splat(size)),
+            ..default()
+        },
+        texture: textures.texture_bevy.clone(),
+        transform: Transform::from_translation(position.extend(1.)),
+        ..Default::default()
+    });
+    player.insert((Player, GridStep::default(), SpatialListener));
+    player
 }
 
-fn _move_player(
+pub fn move_player(
     time: Res<Time>,
     actions: Res<Actions>,
-    mut player_query: Query<&mut Transform, With<Player>>,
+    settings: Res<Settings>,
+    location: Res<PlayerLocation>,
+    maps: Res<Assets<Map>>,
+    mut player_query: Query<(&mut Transform, &mut GridStep), With<Player>>,
 ) {
-    if actions.player_movement.is_none() {
+    let Some(map) = maps.get(&location.map) else {
         return;
+    };
+    let distance = PLAYER_SPEED * time.delta_seconds();
+    for (mut player_transform, mut grid_step) in &mut player_query {
+        let position = player_transform.translation.truncate();
+        let position = match settings.gameplay.movement_mode {
+            MovementMode::Free => {
+                grid_step.target = None;
+                match actions.player_movement {
+                    Some(movement) => move_free(map, position, movement * distance),
+                    None => position,
+                }
+            }
+            MovementMode::Grid => move_on_grid(
+                map,
+                position,
+                actions.player_movement,
+                distance,
+                &mut grid_step,
+            ),
+        };
+        player_transform.translation.x = position.x;
+        player_transform.translation.y = position.y;
+    }
+}
+
+/// Moves one axis at a time, so running diagonally into a wall slides along it
+fn move_free(map: &Map, position: Vec2, movement: Vec2) -> Vec2 {
+    let half_size = Vec2::splat(map.tile_size * PLAYER_HITBOX / 2.);
+    let mut position = position;
+    for axis in [Vec2::X, Vec2::Y] {
+        let step = movement * axis;
+        if step == Vec2::ZERO {
+            continue;
+        }
+        let moved = position + step;
+        if !map.collides(moved, half_size) {
+            position = moved;
+            continue;
+        }
+        // the leading edge ran into the next row or column of tiles, stop right in front of it
+        let direction = step.dot(axis).signum();
+        let leading_edge = moved.dot(axis) + direction * half_size.dot(axis);
+        let tile_edge = ((leading_edge / map.tile_size).round() - direction * 0.5) * map.tile_size;
+        let stop = tile_edge - direction * half_size.dot(axis);
+        let current = position.dot(axis);
+        // never get pushed backwards, e.g. if already overlapping a wall
+        let stop = stop.clamp(current.min(moved.dot(axis)), current.max(moved.dot(axis)));
+        position += axis * (stop - current);
+    }
+    position
+}
+
+/// Walks to the neighbouring tile in the held direction and only stops on tile centers
+fn move_on_grid(
+    map: &Map,
+    position: Vec2,
+    movement: Option<Vec2>,
+    distance: f32,
+    grid_step: &mut GridStep,
+) -> Vec2 {
+    if grid_step.target.is_none() {
+        let Some(movement) = movement else {
+            return position;
+        };
+        // no diagonal steps, the stronger direction wins
+        let direction = if movement.x.abs() > movement.y.abs() {
+            IVec2::new(movement.x.signum() as i32, 0)
+        } else {
+            // tile rows count downwards
+            IVec2::new(0, -movement.y.signum() as i32)
+        };
+        let next = map.world_to_tile(position) + direction;
+        if map.is_solid(next) {
+            return position;
+        }
+        grid_step.target = Some(map.tile_to_world(next));
+    }
+    let Some(target) = grid_step.target else {
+        return position;
+    };
+    let remaining = target - position;
+    if remaining.length() <= distance {
+        grid_step.target = None;
+        return target;
+    }
+    position + remaining.normalize() * distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overworld::map::Map;
+    use bevy::asset::AssetPlugin;
+    use bevy::core::TaskPoolPlugin;
+    use std::time::{Duration, Instant};
+
+    const MAP: &str = "(
+        name: \"Test\",
+        tile_size: 32.0,
+        tiles: [
+            \"######\",
+            \"#....#\",
+            \"#....#\",
+            \"#..#.#\",
+            \"######\",
+        ],
+        spawn: (1, 1),
+        props: [(kind: Crate, tile: (4, 1))],
+    )";
+
+    const FRAME: Duration = Duration::from_millis(20);
+
+    struct TestGame {
+        app: App,
+        now: Instant,
+    }
+
+    impl TestGame {
+        fn new(movement_mode: MovementMode) -> Self {
+            let mut app = App::new();
+            app.add_plugin(TaskPoolPlugin::default())
+                .add_plugin(AssetPlugin::default())
+                .add_asset::<Map>()
+                .init_resource::<Actions>()
+                .add_system(move_player);
+            let mut settings = Settings::default();
+            settings.gameplay.movement_mode = movement_mode;
+            app.insert_resource(settings);
+
+            let map = Map::from_ron(MAP.as_bytes()).unwrap();
+            let start = map.tile_to_world(map.spawn);
+            let map = app.world.resource_mut::<Assets<Map>>().add(map);
+            app.insert_resource(PlayerLocation { map, tile: None });
+            app.world.spawn((
+                Player,
+                GridStep::default(),
+                Transform::from_translation(start.extend(1.)),
+            ));
+
+            let now = Instant::now();
+            let mut time = Time::default();
+            time.update_with_instant(now);
+            app.insert_resource(time);
+            TestGame { app, now }
+        }
+
+        /// Holds `movement` for `frames` frames of 20ms, each moving the player 3 pixels
+        fn walk(&mut self, movement: Option<Vec2>, frames: usize) {
+            self.app.world.resource_mut::<Actions>().player_movement = movement;
+            for _ in 0..frames {
+                self.now += FRAME;
+                self.app
+                    .world
+                    .resource_mut::<Time>()
+                    .update_with_instant(self.now);
+                self.app.update();
+            }
+        }
+
+        fn position(&mut self) -> Vec2 {
+            self.app
+                .world
+                .query_filtered::<&Transform, With<Player>>()
+                .single(&self.app.world)
+                .translation
+                .truncate()
+        }
     }
-    let speed = 150.;
-    let movement = Vec3::new(
-        actions.player_movement.unwrap().x * speed * time.delta_seconds(),
-        actions.player_movement.unwrap().y * speed * time.delta_seconds(),
-        0.,
-    );
-    for mut player_transform in &mut player_query {
-        player_transform.translation += movement;
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            (actual - expected).length() < 0.01,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn free_movement_walks_with_player_speed() {
+        let mut game = TestGame::new(MovementMode::Free);
+        game.walk(Some(Vec2::X), 10);
+
+        assert_near(game.position(), Vec2::new(32. + 30., -32.));
+    }
+
+    #[test]
+    fn free_movement_stops_in_front_of_walls() {
+        let mut game = TestGame::new(MovementMode::Free);
+        game.walk(Some(Vec2::NEG_X), 20);
+
+        // the wall's right edge is at x = 16, the hitbox is 25.6 wide
+        assert_near(game.position(), Vec2::new(16. + 12.8, -32.));
+    }
+
+    #[test]
+    fn free_movement_slides_along_walls() {
+        let mut game = TestGame::new(MovementMode::Free);
+        game.walk(Some(Vec2::new(1., 1.).normalize()), 10);
+
+        let position = game.position();
+        // blocked by the top wall, but still moving right
+        assert_near(Vec2::new(0., position.y), Vec2::new(0., -16. - 12.8));
+        assert!(position.x > 32. + 20., "didn't slide, ended at {position}");
+    }
+
+    #[test]
+    fn free_movement_is_blocked_by_props() {
+        let mut game = TestGame::new(MovementMode::Free);
+        game.walk(Some(Vec2::X), 60);
+
+        // the crate on (4, 1) has its left edge at x = 112
+        assert_near(game.position(), Vec2::new(112. - 12.8, -32.));
+    }
+
+    #[test]
+    fn grid_movement_finishes_the_step() {
+        let mut game = TestGame::new(MovementMode::Grid);
+        game.walk(Some(Vec2::NEG_Y), 1);
+        game.walk(None, 20);
+
+        assert_near(game.position(), Vec2::new(32., -64.));
+    }
+
+    #[test]
+    fn grid_movement_does_not_walk_into_solid_tiles() {
+        let mut game = TestGame::new(MovementMode::Grid);
+        game.walk(Some(Vec2::NEG_Y), 60);
+        // standing on (1, 3) now, with a wall below
+        assert_near(game.position(), Vec2::new(32., -96.));
+
+        game.walk(Some(Vec2::X), 60);
+        // (3, 3) is a wall
+        assert_near(game.position(), Vec2::new(64., -96.));
+    }
+
+    #[test]
+    fn grid_movement_never_moves_diagonally() {
+        let mut game = TestGame::new(MovementMode::Grid);
+        game.walk(Some(Vec2::new(0.8, -0.6)), 11);
+
+        assert_near(game.position(), Vec2::new(64., -32.));
     }
 }
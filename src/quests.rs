@@ -0,0 +1,217 @@
+use crate::data::{RonAsset, RonAssetApp};
+use crate::dialogue::{DialogueState, StartDialogue};
+use crate::flags::{Condition, FlagChange, Flags};
+use crate::loading::DataAssets;
+use crate::quests::journal::JournalPlugin;
+use crate::GameState;
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+pub mod journal;
+
+pub struct QuestPlugin;
+
+/// This plugin keeps track of the player's quests and completes them once all objectives are met
+impl Plugin for QuestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<QuestTable>()
+            .init_resource::<QuestNotifications>()
+            .add_plugin(JournalPlugin)
+            .add_system(complete_quests.in_set(OnUpdate(GameState::Playing)))
+            // last, so dialogues started during the frame come first
+            .add_system(
+                show_quest_notification
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(DialogueState::Closed)),
+            );
+    }
+}
+
+/// Every quest in the game, keyed by quest id
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "8b1e4f62-3c5d-4a7e-b0f9-2d6c8a91e5b3"]
+pub struct QuestTable {
+    pub quests: BTreeMap<String, Quest>,
+}
+
+impl RonAsset for QuestTable {
+    const EXTENSIONS: &'static [&'static str] = &["quests.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.quests.extend(patch.quests);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Quest {
+    pub name: String,
+    pub description: String,
+    pub objectives: Vec<Objective>,
+    /// Applied once all objectives are met
+    #[serde(default)]
+    pub rewards: Vec<FlagChange>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Objective {
+    pub description: String,
+    pub done: Condition,
+}
+
+/// Quests the player has started, by quest id
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Quests {
+    active: BTreeSet<String>,
+    completed: BTreeSet<String>,
+}
+
+impl Quests {
+    /// Does nothing if the quest was started before
+    pub fn start(&mut self, quest: &str) {
+        if !self.is_completed(quest) {
+            self.active.insert(quest.to_string());
+        }
+    }
+
+    /// Does nothing if the quest isn't active
+    pub fn complete(&mut self, quest: &str) {
+        if self.active.remove(quest) {
+            self.completed.insert(quest.to_string());
+        }
+    }
+
+    pub fn is_active(&self, quest: &str) -> bool {
+        self.active.contains(quest)
+    }
+
+    pub fn is_completed(&self, quest: &str) -> bool {
+        self.completed.contains(quest)
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &str> {
+        self.active.iter().map(String::as_str)
+    }
+
+    pub fn completed(&self) -> impl Iterator<Item = &str> {
+        self.completed.iter().map(String::as_str)
+    }
+}
+
+impl QuestTable {
+    pub fn get(&self, id: &str) -> Option<&Quest> {
+        self.quests.get(id)
+    }
+}
+
+impl Quest {
+    pub fn is_done(&self, flags: &Flags, quests: &Quests) -> bool {
+        self.objectives
+            .iter()
+            .all(|objective| objective.done.is_met(flags, quests))
+    }
+}
+
+/// "Quest completed" messages waiting for the dialogue box to be free
+#[derive(Resource, Default)]
+struct QuestNotifications(VecDeque<String>);
+
+fn complete_quests(
+    mut flags: ResMut<Flags>,
+    mut quests: ResMut<Quests>,
+    data_assets: Res<DataAssets>,
+    quest_tables: Res<Assets<QuestTable>>,
+    mut notifications: ResMut<QuestNotifications>,
+) {
+    let Some(quest_table) = quest_tables.get(&data_assets.quests) else {
+        return;
+    };
+    let done: Vec<String> = quests
+        .active()
+        .filter(|id| matches!(quest_table.get(id), Some(quest) if quest.is_done(&flags, &quests)))
+        .map(str::to_string)
+        .collect();
+    for id in done {
+        let quest = &quest_table.quests[&id];
+        info!("Completed the quest \"{}\"", quest.name);
+        for reward in &quest.rewards {
+            flags.apply(reward);
+        }
+        quests.complete(&id);
+        notifications
+            .0
+            .push_back(format!("Quest completed: {}", quest.name));
+    }
+}
+
+/// Shows the next "Quest completed" message, one at a time and only while no other
+/// dialogue is open or about to open
+fn show_quest_notification(
+    mut notifications: ResMut<QuestNotifications>,
+    next_dialogue_state: Res<NextState<DialogueState>>,
+    mut start_dialogue: ResMut<Events<StartDialogue>>,
+    mut reader: Local<ManualEventReader<StartDialogue>>,
+) {
+    let pending = reader.iter(&start_dialogue).count() > 0;
+    if pending || next_dialogue_state.0.is_some() {
+        return;
+    }
+    if let Some(line) = notifications.0.pop_front() {
+        start_dialogue.send(StartDialogue::lines(None, vec![line]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quest() -> Quest {
+        Quest {
+            name: "Clean the beach".to_string(),
+            description: String::new(),
+            objectives: vec![
+                Objective {
+                    description: "Pick up 3 cans".to_string(),
+                    done: Condition::AtLeast("cans".to_string(), 3),
+                },
+                Objective {
+                    description: "Talk to the lifeguard".to_string(),
+                    done: Condition::Flag("met_lifeguard".to_string()),
+                },
+            ],
+            rewards: vec![],
+        }
+    }
+
+    #[test]
+    fn quests_are_started_and_completed_once() {
+        let mut quests = Quests::default();
+        quests.complete("beach");
+        assert!(!quests.is_completed("beach"));
+
+        quests.start("beach");
+        assert!(quests.is_active("beach"));
+        quests.complete("beach");
+        assert!(!quests.is_active("beach"));
+        assert!(quests.is_completed("beach"));
+
+        // finished quests can't be taken again
+        quests.start("beach");
+        assert!(!quests.is_active("beach"));
+        assert_eq!(quests.completed().collect::<Vec<_>>(), ["beach"]);
+    }
+
+    #[test]
+    fn quests_are_done_once_every_objective_is_met() {
+        let quest = quest();
+        let quests = Quests::default();
+        let mut flags = Flags::default();
+        flags.apply(&FlagChange::Assign("cans".to_string(), 3));
+        assert!(!quest.is_done(&flags, &quests));
+        flags.apply(&FlagChange::Set("met_lifeguard".to_string()));
+        assert!(quest.is_done(&flags, &quests));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::actions::Actions;
+use crate::dex::browser::DexState;
+use crate::dialogue::DialogueState;
+use crate::flags::Flags;
+use crate::loading::{DataAssets, FontAssets};
+use crate::quests::{QuestTable, Quests};
+use crate::shop::ShopState;
+use crate::{GameState, PauseState};
+use bevy::prelude::*;
+
+pub struct JournalPlugin;
+
+/// This plugin shows the player's quests and their objectives
+/// The journal is opened from the overworld, which is frozen while it is open
+impl Plugin for JournalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<JournalState>()
+            .configure_set(OnUpdate(GameState::Playing).run_if(in_state(JournalState::Closed)))
+            .add_system(
+                toggle_journal
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(DialogueState::Closed))
+                    .run_if(in_state(ShopState::Closed))
+                    .run_if(in_state(DexState::Closed))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_system(setup_journal.in_schedule(OnEnter(JournalState::Open)))
+            .add_system(cleanup_journal.in_schedule(OnExit(JournalState::Open)))
+            .add_system(close_journal.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum JournalState {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(Component)]
+struct Journal;
+
+const TITLE_COLOR: Color = Color::rgb(0.9, 0.75, 0.4);
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const DONE_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+
+fn toggle_journal(
+    actions: Res<Actions>,
+    journal_state: Res<State<JournalState>>,
+    mut next_state: ResMut<NextState<JournalState>>,
+) {
+    match journal_state.0 {
+        JournalState::Closed if actions.toggle_journal => next_state.set(JournalState::Open),
+        JournalState::Open if actions.toggle_journal || actions.cancel => {
+            next_state.set(JournalState::Closed)
+        }
+        _ => {}
+    }
+}
+
+fn setup_journal(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    data_assets: Res<DataAssets>,
+    quest_tables: Res<Assets<QuestTable>>,
+    quests: Res<Quests>,
+    flags: Res<Flags>,
+) {
+    let Some(quest_table) = quest_tables.get(&data_assets.quests) else {
+        return;
+    };
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.fira_sans.clone(),
+                font_size,
+                color,
+            },
+        )
+        .with_style(Style {
+            margin: UiRect::bottom(Val::Px(6.0)),
+            ..default()
+        })
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(40.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+            z_index: ZIndex::Global(4),
+            ..default()
+        })
+        .insert(Journal)
+        .with_children(|parent| {
+            parent.spawn(text("Journal".to_string(), 50.0, TEXT_COLOR));
+            let mut any_active = false;
+            for quest in quests.active().filter_map(|id| quest_table.get(id)) {
+                any_active = true;
+                parent.spawn(text(quest.name.clone(), 32.0, TITLE_COLOR));
+                parent.spawn(text(quest.description.clone(), 22.0, TEXT_COLOR));
+                for objective in &quest.objectives {
+                    let (mark, color) = if objective.done.is_met(&flags, &quests) {
+                        ("[x]", DONE_COLOR)
+                    } else {
+                        ("[ ]", TEXT_COLOR)
+                    };
+                    parent.spawn(text(
+                        format!("{mark} {}", objective.description),
+                        22.0,
+                        color,
+                    ));
+                }
+            }
+            if !any_active {
+                parent.spawn(text("No open quests".to_string(), 22.0, DONE_COLOR));
+            }
+            let completed: Vec<&str> = quests
+                .completed()
+                .filter_map(|id| quest_table.get(id))
+                .map(|quest| quest.name.as_str())
+                .collect();
+            if !completed.is_empty() {
+                parent.spawn(text("Completed".to_string(), 32.0, TITLE_COLOR));
+                parent.spawn(text(completed.join(", "), 22.0, DONE_COLOR));
+            }
+        });
+}
+
+/// Quitting to the title screen with the journal open
+fn close_journal(mut next_state: ResMut<NextState<JournalState>>) {
+    next_state.set(JournalState::Closed);
+}
+
+fn cleanup_journal(mut commands: Commands, journal: Query<Entity, With<Journal>>) {
+    for entity in &journal {
+        commands.entity(entity).despawn_recursive();
+    }
+}
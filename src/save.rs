@@ -0,0 +1,139 @@
+use crate::dex::Garbagedex;
+use crate::flags::Flags;
+use crate::inventory::Inventory;
+use crate::overworld::npc::DefeatedTrainers;
+use crate::overworld::PlayerLocation;
+use crate::party::PlayerParty;
+use crate::persistence;
+use crate::quests::Quests;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const SAVE_FILE: &str = "save.ron";
+
+/// Everything needed to continue a game from the title screen
+#[derive(Serialize, Deserialize)]
+pub struct SaveGame {
+    /// Asset path of the map the player is on
+    map: String,
+    tile: Option<(i32, i32)>,
+    party: PlayerParty,
+    defeated_trainers: DefeatedTrainers,
+    #[serde(default)]
+    flags: Flags,
+    #[serde(default)]
+    quests: Quests,
+    #[serde(default)]
+    inventory: Inventory,
+    #[serde(default)]
+    dex: Garbagedex,
+}
+
+/// The resources that make up the player's progress
+#[derive(SystemParam)]
+pub struct Progress<'w> {
+    asset_server: Res<'w, AssetServer>,
+    location: Res<'w, PlayerLocation>,
+    party: Res<'w, PlayerParty>,
+    defeated_trainers: Res<'w, DefeatedTrainers>,
+    flags: Res<'w, Flags>,
+    quests: Res<'w, Quests>,
+    inventory: Res<'w, Inventory>,
+    dex: Res<'w, Garbagedex>,
+}
+
+impl SaveGame {
+    pub fn load() -> Option<SaveGame> {
+        match persistence::load(SAVE_FILE) {
+            Ok(save_game) => save_game,
+            Err(error) => {
+                warn!("Failed to read the save game: {error}");
+                None
+            }
+        }
+    }
+
+    /// Writes the current game to disk
+    pub fn store(progress: &Progress) {
+        let Some(map) = progress
+            .asset_server
+            .get_handle_path(&progress.location.map)
+        else {
+            warn!("Can't save, the current map wasn't loaded from a file");
+            return;
+        };
+        let save_game = SaveGame {
+            map: map.path().to_string_lossy().into_owned(),
+            tile: progress.location.tile.map(|tile| (tile.x, tile.y)),
+            party: progress.party.clone(),
+            defeated_trainers: progress.defeated_trainers.clone(),
+            flags: progress.flags.clone(),
+            quests: progress.quests.clone(),
+            inventory: progress.inventory.clone(),
+            dex: progress.dex.clone(),
+        };
+        match persistence::store(SAVE_FILE, &save_game) {
+            Ok(()) => info!("Saved the game"),
+            Err(error) => warn!("Failed to save the game: {error}"),
+        }
+    }
+
+    /// Inserts the saved resources, ready to enter `GameState::Playing`
+    pub fn restore(self, commands: &mut Commands, asset_server: &AssetServer) {
+        commands.insert_resource(PlayerLocation {
+            map: asset_server.load(self.map.as_str()),
+            tile: self.tile.map(IVec2::from),
+        });
+        commands.insert_resource(self.party);
+        commands.insert_resource(self.defeated_trainers);
+        commands.insert_resource(self.flags);
+        commands.insert_resource(self.quests);
+        commands.insert_resource(self.inventory);
+        commands.insert_resource(self.dex);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::party::PartyMember;
+
+    #[test]
+    fn save_games_survive_a_round_trip() {
+        let mut flags = Flags::default();
+        flags.0.insert("cans".to_string(), 3);
+        let mut quests = Quests::default();
+        quests.start("beach");
+        quests.start("dump");
+        quests.complete("dump");
+        let mut inventory = Inventory::default();
+        inventory.add("rare_candy", 2);
+        inventory.money = 42;
+        let save_game = SaveGame {
+            map: "maps/town.map.ron".to_string(),
+            tile: Some((3, 4)),
+            party: PlayerParty {
+                members: vec![PartyMember::wild("trashmon", 5)],
+            },
+            defeated_trainers: DefeatedTrainers::default(),
+            flags,
+            quests,
+            inventory,
+            dex: Garbagedex::default(),
+        };
+
+        let text = ron::to_string(&save_game).unwrap();
+        let loaded: SaveGame = ron::from_str(&text).unwrap();
+
+        assert_eq!(loaded.map, save_game.map);
+        assert_eq!(loaded.tile, Some((3, 4)));
+        assert_eq!(loaded.party.members, save_game.party.members);
+        assert_eq!(loaded.flags.get("cans"), 3);
+        assert!(loaded.quests.is_active("beach"));
+        assert!(loaded.quests.is_completed("dump"));
+        assert!(!loaded.quests.is_active("dump"));
+        assert_eq!(loaded.inventory.items, save_game.inventory.items);
+        assert_eq!(loaded.inventory.money, 42);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::persistence;
+use crate::settings::controls::ControlsMenuPlugin;
+use crate::settings::menu::SettingsMenuPlugin;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+pub mod controls;
+pub mod menu;
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+pub const RESOLUTIONS: [(f32, f32); 4] =
+    [(1024., 720.), (1280., 720.), (1600., 900.), (1920., 1080.)];
+
+const VOLUME_STEP: f64 = 0.1;
+const ANIMATION_SPEED_STEP: f32 = 0.25;
+const MIN_ANIMATION_SPEED: f32 = 0.25;
+const MAX_ANIMATION_SPEED: f32 = 3.0;
+
+pub struct SettingsPlugin;
+
+/// This plugin loads the [`Settings`] from disk, applies them to the window
+/// and writes them back whenever they change
+/// If the app already has the settings, e.g. to open the window with them, those are kept
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<Settings>() {
+            app.insert_resource(Settings::load());
+        }
+        app.add_plugin(SettingsMenuPlugin)
+            .add_plugin(ControlsMenuPlugin)
+            .add_system(apply_video_settings.run_if(resource_changed::<Settings>()))
+            .add_system(store_settings.run_if(resource_changed::<Settings>()));
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub video: VideoSettings,
+    pub gameplay: GameplaySettings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f64,
+    pub music_volume: f64,
+    pub sfx_volume: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct VideoSettings {
+    pub window_mode: WindowModeSetting,
+    pub resolution: (f32, f32),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GameplaySettings {
+    /// Multiplier for every battle animation, `1.0` being normal speed
+    pub battle_animation_speed: f32,
+    pub text_speed: TextSpeed,
+    pub movement_mode: MovementMode,
+}
+
+/// One adjustable line of the settings menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingKind {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    WindowMode,
+    Resolution,
+    BattleAnimationSpeed,
+    TextSpeed,
+    MovementMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowModeSetting {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextSpeed {
+    Slow,
+    Normal,
+    Fast,
+    Instant,
+}
+
+/// How the player walks through the overworld
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovementMode {
+    /// Walk anywhere, sliding along walls
+    Free,
+    /// Walk from tile to tile
+    Grid,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master_volume: 1.0,
+            music_volume: 0.7,
+            sfx_volume: 0.8,
+        }
+    }
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        VideoSettings {
+            window_mode: WindowModeSetting::Windowed,
+            resolution: RESOLUTIONS[0],
+        }
+    }
+}
+
+impl Default for GameplaySettings {
+    fn default() -> Self {
+        GameplaySettings {
+            battle_animation_speed: 1.0,
+            text_speed: TextSpeed::Normal,
+            movement_mode: MovementMode::Free,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        persistence::load_or_default::<Settings>(SETTINGS_FILE).sanitized()
+    }
+
+    /// The primary window as the video settings describe it, so they apply from the start
+    pub fn window(&self, title: &str) -> Window {
+        let (width, height) = self.video.resolution;
+        Window {
+            title: title.to_string(),
+            mode: self.video.window_mode.window_mode(),
+            resolution: (width, height).into(),
+            // the size only changes through the resolution setting
+            resizable: false,
+            canvas: Some("#bevy".to_owned()),
+            ..default()
+        }
+    }
+
+    /// Clamps everything into range, so a hand edited file can't break the game
+    fn sanitized(mut self) -> Self {
+        let clamp_volume = |volume: f64| {
+            if volume.is_finite() {
+                volume.clamp(0.0, 1.0)
+            } else {
+                1.0
+            }
+        };
+        self.audio.master_volume = clamp_volume(self.audio.master_volume);
+        self.audio.music_volume = clamp_volume(self.audio.music_volume);
+        self.audio.sfx_volume = clamp_volume(self.audio.sfx_volume);
+        if !RESOLUTIONS.contains(&self.video.resolution) {
+            self.video.resolution = VideoSettings::default().resolution;
+        }
+        let speed = self.gameplay.battle_animation_speed;
+        self.gameplay.battle_animation_speed = if speed.is_finite() {
+            speed.clamp(MIN_ANIMATION_SPEED, MAX_ANIMATION_SPEED)
+        } else {
+            1.0
+        };
+        self
+    }
+
+    pub fn music_volume(&self) -> f64 {
+        self.audio.master_volume * self.audio.music_volume
+    }
+
+    pub fn sfx_volume(&self) -> f64 {
+        self.audio.master_volume * self.audio.sfx_volume
+    }
+
+    /// Moves the given setting one notch up (`direction` > 0) or down
+    pub fn step(&mut self, kind: SettingKind, direction: i32) {
+        let step_volume = |volume: f64| {
+            // round to whole steps, so repeated clicking doesn't accumulate float noise
+            let steps = (volume / VOLUME_STEP).round() + direction as f64;
+            (steps * VOLUME_STEP).clamp(0.0, 1.0)
+        };
+        match kind {
+            SettingKind::MasterVolume => {
+                self.audio.master_volume = step_volume(self.audio.master_volume)
+            }
+            SettingKind::MusicVolume => {
+                self.audio.music_volume = step_volume(self.audio.music_volume)
+            }
+            SettingKind::SfxVolume => self.audio.sfx_volume = step_volume(self.audio.sfx_volume),
+            SettingKind::WindowMode => {
+                self.video.window_mode =
+                    cycle(&WindowModeSetting::ALL, self.video.window_mode, direction)
+            }
+            SettingKind::Resolution => {
+                self.video.resolution = cycle(&RESOLUTIONS, self.video.resolution, direction)
+            }
+            SettingKind::BattleAnimationSpeed => {
+                self.gameplay.battle_animation_speed = (self.gameplay.battle_animation_speed
+                    + direction as f32 * ANIMATION_SPEED_STEP)
+                    .clamp(MIN_ANIMATION_SPEED, MAX_ANIMATION_SPEED)
+            }
+            SettingKind::TextSpeed => {
+                self.gameplay.text_speed =
+                    cycle(&TextSpeed::ALL, self.gameplay.text_speed, direction)
+            }
+            SettingKind::MovementMode => {
+                self.gameplay.movement_mode =
+                    cycle(&MovementMode::ALL, self.gameplay.movement_mode, direction)
+            }
+        }
+    }
+
+    /// The current value of a setting, formatted for the settings menu
+    pub fn describe(&self, kind: SettingKind) -> String {
+        let percent = |volume: f64| format!("{}%", (volume * 100.).round());
+        match kind {
+            SettingKind::MasterVolume => percent(self.audio.master_volume),
+            SettingKind::MusicVolume => percent(self.audio.music_volume),
+            SettingKind::SfxVolume => percent(self.audio.sfx_volume),
+            SettingKind::WindowMode => self.video.window_mode.label().to_string(),
+            SettingKind::Resolution => {
+                let (width, height) = self.video.resolution;
+                format!("{width}x{height}")
+            }
+            SettingKind::BattleAnimationSpeed => {
+                format!("{}x", self.gameplay.battle_animation_speed)
+            }
+            SettingKind::TextSpeed => self.gameplay.text_speed.label().to_string(),
+            SettingKind::MovementMode => self.gameplay.movement_mode.label().to_string(),
+        }
+    }
+}
+
+impl SettingKind {
+    pub const ALL: [SettingKind; 8] = [
+        SettingKind::MasterVolume,
+        SettingKind::MusicVolume,
+        SettingKind::SfxVolume,
+        SettingKind::WindowMode,
+        SettingKind::Resolution,
+        SettingKind::BattleAnimationSpeed,
+        SettingKind::TextSpeed,
+        SettingKind::MovementMode,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingKind::MasterVolume => "Master volume",
+            SettingKind::MusicVolume => "Music volume",
+            SettingKind::SfxVolume => "Effects volume",
+            SettingKind::WindowMode => "Window mode",
+            SettingKind::Resolution => "Resolution",
+            SettingKind::BattleAnimationSpeed => "Animation speed",
+            SettingKind::TextSpeed => "Text speed",
+            SettingKind::MovementMode => "Movement",
+        }
+    }
+}
+
+impl WindowModeSetting {
+    const ALL: [WindowModeSetting; 3] = [
+        WindowModeSetting::Windowed,
+        WindowModeSetting::Borderless,
+        WindowModeSetting::Fullscreen,
+    ];
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::Borderless => WindowMode::BorderlessFullscreen,
+            WindowModeSetting::Fullscreen => WindowMode::SizedFullscreen,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            WindowModeSetting::Windowed => "Windowed",
+            WindowModeSetting::Borderless => "Borderless",
+            WindowModeSetting::Fullscreen => "Fullscreen",
+        }
+    }
+}
+
+impl TextSpeed {
+    const ALL: [TextSpeed; 4] = [
+        TextSpeed::Slow,
+        TextSpeed::Normal,
+        TextSpeed::Fast,
+        TextSpeed::Instant,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            TextSpeed::Slow => "Slow",
+            TextSpeed::Normal => "Normal",
+            TextSpeed::Fast => "Fast",
+            TextSpeed::Instant => "Instant",
+        }
+    }
+
+    /// How fast dialogue text is typed out, `None` shows whole lines at once
+    pub fn characters_per_second(&self) -> Option<f32> {
+        match self {
+            TextSpeed::Slow => Some(20.),
+            TextSpeed::Normal => Some(40.),
+            TextSpeed::Fast => Some(80.),
+            TextSpeed::Instant => None,
+        }
+    }
+}
+
+impl MovementMode {
+    const ALL: [MovementMode; 2] = [MovementMode::Free, MovementMode::Grid];
+
+    fn label(&self) -> &'static str {
+        match self {
+            MovementMode::Free => "Free",
+            MovementMode::Grid => "Grid",
+        }
+    }
+}
+
+/// Steps through `options`, wrapping around at both ends
+fn cycle<T: PartialEq + Copy>(options: &[T], current: T, direction: i32) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0) as i32;
+    let next = (index + direction).rem_euclid(options.len() as i32);
+    options[next as usize]
+}
+
+fn apply_video_settings(
+    settings: Res<Settings>,
+    mut primary_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = primary_window.get_single_mut() else {
+        return;
+    };
+    let mode = settings.video.window_mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let (width, height) = settings.video.resolution;
+    if window.resolution.width() != width || window.resolution.height() != height {
+        window.resolution.set(width, height);
+    }
+}
+
+fn store_settings(settings: Res<Settings>) {
+    // the resource counts as changed when it is first inserted, no need to write it back then
+    if settings.is_added() {
+        return;
+    }
+    if let Err(error) = persistence::store(SETTINGS_FILE, &*settings) {
+        warn!("Failed to save settings: {error}");
+    }
+}
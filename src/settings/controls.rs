@@ -0,0 +1,271 @@
+use crate::actions::bindings::{InputBindings, KEYS_PER_CONTROL};
+use crate::actions::game_control::GameControl;
+use crate::actions::Actions;
+use crate::loading::FontAssets;
+use crate::settings::menu::SettingsMenuState;
+use crate::ui::focus::{CancelButton, Focus};
+use crate::ui::{spawn_button, ButtonColors};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+pub struct ControlsMenuPlugin;
+
+/// This plugin draws the key binding screen and captures new keys for the [`InputBindings`]
+/// The screen is only drawn during the State `SettingsMenuState::Controls`
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_system(setup_controls_menu.in_schedule(OnEnter(SettingsMenuState::Controls)))
+            .add_systems(
+                (
+                    click_controls_button,
+                    close_controls_menu,
+                    capture_key
+                        .after(close_controls_menu)
+                        .before(click_controls_button),
+                    update_binding_labels.after(capture_key),
+                    suspend_focus_while_rebinding.after(capture_key),
+                )
+                    .in_set(OnUpdate(SettingsMenuState::Controls)),
+            )
+            .add_system(cleanup_controls_menu.in_schedule(OnExit(SettingsMenuState::Controls)));
+    }
+}
+
+/// The binding slot waiting for a key press, if any
+#[derive(Resource, Default)]
+struct Rebinding {
+    target: Option<BindingSlot>,
+    message: String,
+}
+
+#[derive(Component)]
+struct ControlsMenu;
+
+#[derive(Component)]
+struct StatusText;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+struct BindingSlot {
+    control: GameControl,
+    slot: usize,
+}
+
+#[derive(Component)]
+enum ControlsButton {
+    ResetToDefaults,
+    Back,
+}
+
+fn setup_controls_menu(
+    mut commands: Commands,
+    mut rebinding: ResMut<Rebinding>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    *rebinding = Rebinding::default();
+    let text_style = TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: 24.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.1, 0.1, 0.1, 0.95).into(),
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(ControlsMenu)
+        .with_children(|parent| {
+            for control in GameControl::ALL {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(TextBundle::from_section(
+                            control.label(),
+                            text_style.clone(),
+                        ))
+                        .insert(Style {
+                            size: Size::width(Val::Px(150.0)),
+                            ..default()
+                        });
+                        for slot in 0..KEYS_PER_CONTROL {
+                            row.spawn(ButtonBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(160.0), Val::Px(36.0)),
+                                    margin: UiRect::all(Val::Px(2.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: button_colors.normal.into(),
+                                ..default()
+                            })
+                            .insert(BindingSlot { control, slot })
+                            .with_children(|button| {
+                                button.spawn(TextBundle::from_section("", text_style.clone()));
+                            });
+                        }
+                    });
+            }
+            parent
+                .spawn(TextBundle::from_section("", text_style.clone()))
+                .insert(Style {
+                    margin: UiRect::vertical(Val::Px(10.0)),
+                    ..default()
+                })
+                .insert(StatusText);
+            parent
+                .spawn(NodeBundle::default())
+                .with_children(|buttons| {
+                    spawn_button(
+                        buttons,
+                        "Defaults",
+                        font_assets.fira_sans.clone(),
+                        &button_colors,
+                        Size::new(Val::Px(200.0), Val::Px(60.0)),
+                    )
+                    .insert(ControlsButton::ResetToDefaults);
+                    spawn_button(
+                        buttons,
+                        "Back",
+                        font_assets.fira_sans.clone(),
+                        &button_colors,
+                        Size::new(Val::Px(200.0), Val::Px(60.0)),
+                    )
+                    .insert((ControlsButton::Back, CancelButton));
+                });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn click_controls_button(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<InputBindings>,
+    mut next_state: ResMut<NextState<SettingsMenuState>>,
+    slot_query: Query<(&Interaction, &BindingSlot), Changed<Interaction>>,
+    button_query: Query<(&Interaction, &ControlsButton), Changed<Interaction>>,
+) {
+    for (interaction, slot) in &slot_query {
+        if *interaction == Interaction::Clicked {
+            rebinding.target = Some(*slot);
+            rebinding.message = format!(
+                "Press a key for {} (Escape to cancel, Delete to clear)",
+                slot.control.label()
+            );
+        }
+    }
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            ControlsButton::ResetToDefaults => {
+                *bindings = InputBindings::default();
+                *rebinding = Rebinding::default();
+            }
+            ControlsButton::Back => next_state.set(SettingsMenuState::Open),
+        }
+    }
+}
+
+/// Go back to the settings menu, unless a key is being captured
+fn close_controls_menu(
+    actions: Res<Actions>,
+    rebinding: Res<Rebinding>,
+    mut next_state: ResMut<NextState<SettingsMenuState>>,
+) {
+    if rebinding.target.is_none() && actions.toggle_pause {
+        next_state.set(SettingsMenuState::Open);
+    }
+}
+
+fn capture_key(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    let Some(target) = rebinding.target else {
+        return;
+    };
+    let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+        return;
+    };
+    let result = match key {
+        KeyCode::Escape => Ok(()),
+        KeyCode::Delete => {
+            bindings.unbind(target.control, target.slot);
+            Ok(())
+        }
+        key => bindings.bind(target.control, target.slot, key),
+    };
+    match result {
+        Ok(()) => *rebinding = Rebinding::default(),
+        Err(error) => rebinding.message = format!("{error}, try another key"),
+    }
+}
+
+/// Keys pressed while waiting for a new binding must not move or press the focused button
+fn suspend_focus_while_rebinding(rebinding: Res<Rebinding>, mut focus: ResMut<Focus>) {
+    if rebinding.is_changed() {
+        focus.suspended = rebinding.target.is_some();
+    }
+}
+
+fn update_binding_labels(
+    rebinding: Res<Rebinding>,
+    bindings: Res<InputBindings>,
+    slot_query: Query<(&BindingSlot, &Children)>,
+    mut text_query: Query<&mut Text, Without<StatusText>>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+) {
+    if !rebinding.is_changed() && !bindings.is_changed() {
+        return;
+    }
+    for (slot, children) in &slot_query {
+        let label = if rebinding.target == Some(*slot) {
+            "...".to_string()
+        } else {
+            bindings
+                .keys(slot.control)
+                .get(slot.slot)
+                .map(|key| format!("{key:?}"))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let mut text_iter = text_query.iter_many_mut(children);
+        while let Some(mut text) = text_iter.fetch_next() {
+            text.sections[0].value = label.clone();
+        }
+    }
+    for mut text in &mut status_query {
+        text.sections[0].value = rebinding.message.clone();
+    }
+}
+
+fn cleanup_controls_menu(
+    mut commands: Commands,
+    mut rebinding: ResMut<Rebinding>,
+    mut focus: ResMut<Focus>,
+    controls_menu: Query<Entity, With<ControlsMenu>>,
+) {
+    *rebinding = Rebinding::default();
+    focus.suspended = false;
+    for entity in &controls_menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,184 @@
+use crate::actions::Actions;
+use crate::loading::FontAssets;
+use crate::settings::{SettingKind, Settings};
+use crate::ui::focus::CancelButton;
+use crate::ui::{spawn_button, ButtonColors};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+pub struct SettingsMenuPlugin;
+
+/// This plugin draws the settings menu on top of whatever screen opened it
+/// The menu is only drawn during the State `SettingsMenuState::Open`
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<SettingsMenuState>()
+            .add_system(setup_settings_menu.in_schedule(OnEnter(SettingsMenuState::Open)))
+            .add_systems(
+                (
+                    click_settings_button,
+                    update_setting_values,
+                    close_settings_menu,
+                )
+                    .in_set(OnUpdate(SettingsMenuState::Open)),
+            )
+            .add_system(cleanup_settings_menu.in_schedule(OnExit(SettingsMenuState::Open)));
+    }
+}
+
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum SettingsMenuState {
+    #[default]
+    Closed,
+    Open,
+    // The key binding screen, reached through the settings menu
+    Controls,
+}
+
+#[derive(Component)]
+struct SettingsMenu;
+
+#[derive(Component)]
+struct SettingValue(SettingKind);
+
+#[derive(Component)]
+enum SettingsButton {
+    Decrease(SettingKind),
+    Increase(SettingKind),
+    Controls,
+    Back,
+}
+
+fn setup_settings_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    settings: Res<Settings>,
+) {
+    let text_style = TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: 30.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let arrow_size = Size::new(Val::Px(50.0), Val::Px(50.0));
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.1, 0.1, 0.1, 0.95).into(),
+            focus_policy: FocusPolicy::Block,
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert(SettingsMenu)
+        .with_children(|parent| {
+            for kind in SettingKind::ALL {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(TextBundle::from_section(kind.label(), text_style.clone()))
+                            .insert(Style {
+                                size: Size::width(Val::Px(250.0)),
+                                ..default()
+                            });
+                        spawn_button(
+                            row,
+                            "<",
+                            font_assets.fira_sans.clone(),
+                            &button_colors,
+                            arrow_size,
+                        )
+                        .insert(SettingsButton::Decrease(kind));
+                        row.spawn(TextBundle::from_section(
+                            settings.describe(kind),
+                            text_style.clone(),
+                        ))
+                        .insert(Style {
+                            size: Size::width(Val::Px(180.0)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        })
+                        .insert(SettingValue(kind));
+                        spawn_button(
+                            row,
+                            ">",
+                            font_assets.fira_sans.clone(),
+                            &button_colors,
+                            arrow_size,
+                        )
+                        .insert(SettingsButton::Increase(kind));
+                    });
+            }
+            spawn_button(
+                parent,
+                "Controls",
+                font_assets.fira_sans.clone(),
+                &button_colors,
+                Size::new(Val::Px(200.0), Val::Px(60.0)),
+            )
+            .insert(SettingsButton::Controls);
+            spawn_button(
+                parent,
+                "Back",
+                font_assets.fira_sans.clone(),
+                &button_colors,
+                Size::new(Val::Px(200.0), Val::Px(60.0)),
+            )
+            .insert((SettingsButton::Back, CancelButton));
+        });
+}
+
+fn click_settings_button(
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<SettingsMenuState>>,
+    interaction_query: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            SettingsButton::Decrease(kind) => settings.step(*kind, -1),
+            SettingsButton::Increase(kind) => settings.step(*kind, 1),
+            SettingsButton::Controls => next_state.set(SettingsMenuState::Controls),
+            SettingsButton::Back => next_state.set(SettingsMenuState::Closed),
+        }
+    }
+}
+
+fn update_setting_values(settings: Res<Settings>, mut values: Query<(&mut Text, &SettingValue)>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut text, value) in &mut values {
+        text.sections[0].value = settings.describe(value.0);
+    }
+}
+
+/// The menu key doubles as "back" while the settings are open
+fn close_settings_menu(
+    actions: Res<Actions>,
+    mut next_state: ResMut<NextState<SettingsMenuState>>,
+) {
+    if actions.toggle_pause {
+        next_state.set(SettingsMenuState::Closed);
+    }
+}
+
+fn cleanup_settings_menu(mut commands: Commands, settings_menu: Query<Entity, With<SettingsMenu>>) {
+    for entity in &settings_menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
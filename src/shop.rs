@@ -0,0 +1,263 @@
+use crate::data::{GameData, RonAssetApp};
+use crate::inventory::Inventory;
+use crate::loading::FontAssets;
+use crate::shop::stock::ShopTable;
+use crate::ui::focus::CancelButton;
+use crate::ui::{spawn_button, ButtonColors};
+use crate::{GameState, PauseState};
+use bevy::prelude::*;
+
+pub mod stock;
+
+pub struct ShopPlugin;
+
+/// This plugin lets the player buy and sell items for coins
+/// Shops are opened from dialogue and freeze the overworld while they are open
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<ShopTable>()
+            .add_state::<ShopState>()
+            .configure_set(OnUpdate(GameState::Playing).run_if(in_state(ShopState::Closed)))
+            .configure_set(OnUpdate(ShopState::Open).run_if(in_state(PauseState::Running)))
+            .add_system(open_shop.run_if(resource_added::<ActiveShop>()))
+            .add_systems(
+                (click_shop_button, draw_shop)
+                    .chain()
+                    .in_set(OnUpdate(ShopState::Open)),
+            )
+            .add_system(setup_shop.in_schedule(OnEnter(ShopState::Open)))
+            .add_system(cleanup_shop.in_schedule(OnExit(ShopState::Open)))
+            .add_system(close_shop.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ShopState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// The shop the player is in, inserting it opens the shop
+#[derive(Resource)]
+pub struct ActiveShop {
+    /// Shop id in the [`ShopTable`]
+    id: String,
+    /// Result of the last purchase or sale
+    message: String,
+    redraw: bool,
+}
+
+impl ActiveShop {
+    pub fn new(id: &str) -> Self {
+        ActiveShop {
+            id: id.to_string(),
+            message: String::new(),
+            redraw: true,
+        }
+    }
+}
+
+#[derive(Component)]
+struct ShopMenu;
+
+#[derive(Component)]
+enum ShopButton {
+    Buy(String),
+    Sell(String),
+    Close,
+}
+
+const BUTTON_SIZE: Size = Size::new(Val::Px(380.0), Val::Px(50.0));
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const MONEY_COLOR: Color = Color::rgb(0.9, 0.75, 0.4);
+
+fn open_shop(
+    mut commands: Commands,
+    game_data: GameData,
+    shop: Res<ActiveShop>,
+    mut next_state: ResMut<NextState<ShopState>>,
+) {
+    if game_data
+        .shops()
+        .and_then(|shops| shops.get(&shop.id))
+        .is_none()
+    {
+        warn!("Unknown shop \"{}\"", shop.id);
+        commands.remove_resource::<ActiveShop>();
+        return;
+    }
+    next_state.set(ShopState::Open);
+}
+
+fn setup_shop(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(40.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+            z_index: ZIndex::Global(4),
+            ..default()
+        })
+        .insert(ShopMenu);
+}
+
+fn click_shop_button(
+    game_data: GameData,
+    mut shop: ResMut<ActiveShop>,
+    mut inventory: ResMut<Inventory>,
+    mut next_state: ResMut<NextState<ShopState>>,
+    interaction_query: Query<(&Interaction, &ShopButton), Changed<Interaction>>,
+) {
+    let (Some(shops), Some(items)) = (game_data.shops(), game_data.items()) else {
+        return;
+    };
+    let Some(current) = shops.get(&shop.id) else {
+        return;
+    };
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let name = |id: &str| {
+            items
+                .get(id)
+                .map_or(id.to_string(), |item| item.name.clone())
+        };
+        let message = match button {
+            ShopButton::Buy(item) => match current.buy(item, &mut inventory) {
+                Ok(price) => format!("Bought {} for {price} coins", name(item)),
+                Err(error) => error.to_string(),
+            },
+            ShopButton::Sell(item) => match current.sell(item, items, &mut inventory) {
+                Ok(price) => format!("Sold {} for {price} coins", name(item)),
+                Err(error) => error.to_string(),
+            },
+            ShopButton::Close => {
+                next_state.set(ShopState::Closed);
+                return;
+            }
+        };
+        shop.message = message;
+        shop.redraw = true;
+    }
+}
+
+fn draw_shop(
+    mut commands: Commands,
+    game_data: GameData,
+    mut shop: ResMut<ActiveShop>,
+    inventory: Res<Inventory>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    menu: Query<Entity, With<ShopMenu>>,
+) {
+    if !shop.redraw {
+        return;
+    }
+    let (Some(shops), Some(items)) = (game_data.shops(), game_data.items()) else {
+        return;
+    };
+    let Some(current) = shops.get(&shop.id) else {
+        return;
+    };
+    shop.redraw = false;
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.fira_sans.clone(),
+                font_size,
+                color,
+            },
+        )
+        .with_style(Style {
+            margin: UiRect::bottom(Val::Px(6.0)),
+            ..default()
+        })
+    };
+    let column = NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            margin: UiRect::horizontal(Val::Px(20.0)),
+            ..default()
+        },
+        ..default()
+    };
+    let name = |id: &str| {
+        items
+            .get(id)
+            .map_or(id.to_string(), |item| item.name.clone())
+    };
+    for entity in &menu {
+        let mut menu = commands.entity(entity);
+        menu.despawn_descendants();
+        menu.with_children(|parent| {
+            parent.spawn(text(current.name.clone(), 50.0, TEXT_COLOR));
+            parent.spawn(text(
+                format!("{} coins", inventory.money),
+                32.0,
+                MONEY_COLOR,
+            ));
+            parent.spawn(text(shop.message.clone(), 22.0, TEXT_COLOR));
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                parent.spawn(column.clone()).with_children(|parent| {
+                    parent.spawn(text("Buy".to_string(), 32.0, TEXT_COLOR));
+                    for stock in &current.stock {
+                        spawn_button(
+                            parent,
+                            &format!("{} {}", name(&stock.item), stock.price),
+                            font_assets.fira_sans.clone(),
+                            &button_colors,
+                            BUTTON_SIZE,
+                        )
+                        .insert(ShopButton::Buy(stock.item.clone()));
+                    }
+                });
+                parent.spawn(column.clone()).with_children(|parent| {
+                    parent.spawn(text("Sell".to_string(), 32.0, TEXT_COLOR));
+                    for (item, count) in &inventory.items {
+                        let Some(price) = current.sell_price(item, items) else {
+                            continue;
+                        };
+                        spawn_button(
+                            parent,
+                            &format!("{} x{count} {price}", name(item)),
+                            font_assets.fira_sans.clone(),
+                            &button_colors,
+                            BUTTON_SIZE,
+                        )
+                        .insert(ShopButton::Sell(item.clone()));
+                    }
+                });
+            });
+            spawn_button(
+                parent,
+                "Leave",
+                font_assets.fira_sans.clone(),
+                &button_colors,
+                BUTTON_SIZE,
+            )
+            .insert((ShopButton::Close, CancelButton));
+        });
+    }
+}
+
+/// Quitting to the title screen with the shop open
+fn close_shop(mut next_state: ResMut<NextState<ShopState>>) {
+    next_state.set(ShopState::Closed);
+}
+
+fn cleanup_shop(mut commands: Commands, menu: Query<Entity, With<ShopMenu>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<ActiveShop>();
+}
@@ -0,0 +1,247 @@
+use crate::data::RonAsset;
+use crate::inventory::items::ItemTable;
+use crate::inventory::Inventory;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Every shop in the game, keyed by shop id
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "d3f81b6a-7e24-4c09-9a5e-61b2c7f04e18"]
+pub struct ShopTable {
+    pub shops: BTreeMap<String, Shop>,
+}
+
+impl RonAsset for ShopTable {
+    const EXTENSIONS: &'static [&'static str] = &["shops.ron"];
+
+    fn merge(&mut self, patch: Self) {
+        self.shops.extend(patch.shops);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Shop {
+    pub name: String,
+    pub stock: Vec<Stock>,
+    pub buyback: Buyback,
+}
+
+/// An item the shop sells
+#[derive(Deserialize, Clone, Debug)]
+pub struct Stock {
+    pub item: String,
+    pub price: u32,
+}
+
+/// How the shop pays for items the player sells
+#[derive(Deserialize, Clone, Debug)]
+pub struct Buyback {
+    /// Share of the price that is paid back, the shop's own price for stocked items and the
+    /// item's value otherwise
+    pub rate: f32,
+    /// The shop only buys items it sells itself
+    #[serde(default)]
+    pub only_stocked: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShopError {
+    NotInStock,
+    NotEnoughMoney {
+        price: u32,
+    },
+    /// The shop doesn't buy the item
+    NotWanted,
+    /// The player has none of the item left
+    NoneLeft,
+}
+
+impl fmt::Display for ShopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShopError::NotInStock => write!(f, "That's not for sale here"),
+            ShopError::NotEnoughMoney { price } => write!(f, "You need {price} coins for that"),
+            ShopError::NotWanted => write!(f, "The shop doesn't want that"),
+            ShopError::NoneLeft => write!(f, "You don't have any of that"),
+        }
+    }
+}
+
+impl ShopTable {
+    pub fn get(&self, id: &str) -> Option<&Shop> {
+        self.shops.get(id)
+    }
+}
+
+impl Shop {
+    pub fn price(&self, item: &str) -> Option<u32> {
+        self.stock
+            .iter()
+            .find(|stock| stock.item == item)
+            .map(|stock| stock.price)
+    }
+
+    /// What the shop pays for one of the item, `None` if it won't take it
+    pub fn sell_price(&self, item: &str, items: &ItemTable) -> Option<u32> {
+        let base = match self.price(item) {
+            Some(price) => price,
+            None if self.buyback.only_stocked => return None,
+            None => items.get(item)?.value,
+        };
+        let price = (base as f32 * self.buyback.rate).floor() as u32;
+        (price > 0).then_some(price)
+    }
+
+    /// Buys one of the item, returns the price paid
+    pub fn buy(&self, item: &str, inventory: &mut Inventory) -> Result<u32, ShopError> {
+        let price = self.price(item).ok_or(ShopError::NotInStock)?;
+        if inventory.money < price {
+            return Err(ShopError::NotEnoughMoney { price });
+        }
+        inventory.money -= price;
+        inventory.add(item, 1);
+        Ok(price)
+    }
+
+    /// Sells one of the item, returns the money received
+    pub fn sell(
+        &self,
+        item: &str,
+        items: &ItemTable,
+        inventory: &mut Inventory,
+    ) -> Result<u32, ShopError> {
+        let price = self.sell_price(item, items).ok_or(ShopError::NotWanted)?;
+        if !inventory.take(item) {
+            return Err(ShopError::NoneLeft);
+        }
+        inventory.money = inventory.money.saturating_add(price);
+        Ok(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::items::{Item, ItemEffect};
+
+    fn item_table() -> ItemTable {
+        let item = |value| Item {
+            name: String::new(),
+            description: String::new(),
+            value,
+            effect: Some(ItemEffect::Cure),
+            held: vec![],
+            icon: None,
+        };
+        ItemTable {
+            items: BTreeMap::from([
+                ("potion".to_string(), item(15)),
+                ("antidote".to_string(), item(10)),
+                ("junk".to_string(), item(0)),
+            ]),
+        }
+    }
+
+    fn shop(only_stocked: bool) -> Shop {
+        Shop {
+            name: "Test Shop".to_string(),
+            stock: vec![Stock {
+                item: "potion".to_string(),
+                price: 30,
+            }],
+            buyback: Buyback {
+                rate: 0.5,
+                only_stocked,
+            },
+        }
+    }
+
+    fn inventory(money: u32) -> Inventory {
+        Inventory {
+            items: BTreeMap::new(),
+            money,
+        }
+    }
+
+    #[test]
+    fn buying_takes_the_price() {
+        let mut inventory = inventory(50);
+        assert_eq!(shop(false).buy("potion", &mut inventory), Ok(30));
+        assert_eq!(inventory.money, 20);
+        assert_eq!(inventory.items.get("potion"), Some(&1));
+    }
+
+    #[test]
+    fn buying_with_the_exact_balance() {
+        let mut inventory = inventory(30);
+        assert_eq!(shop(false).buy("potion", &mut inventory), Ok(30));
+        assert_eq!(inventory.money, 0);
+    }
+
+    #[test]
+    fn buying_without_enough_money_changes_nothing() {
+        let mut inventory = inventory(29);
+        assert_eq!(
+            shop(false).buy("potion", &mut inventory),
+            Err(ShopError::NotEnoughMoney { price: 30 })
+        );
+        assert_eq!(inventory.money, 29);
+        assert!(inventory.items.is_empty());
+    }
+
+    #[test]
+    fn buying_what_is_not_stocked() {
+        let mut inventory = inventory(100);
+        assert_eq!(
+            shop(false).buy("antidote", &mut inventory),
+            Err(ShopError::NotInStock)
+        );
+        assert_eq!(inventory.money, 100);
+    }
+
+    #[test]
+    fn selling_follows_the_buyback_rules() {
+        let items = item_table();
+        let mut inventory = inventory(0);
+        inventory.add("potion", 1);
+        inventory.add("antidote", 1);
+        inventory.add("junk", 1);
+        // stocked items go by the shop's price, others by their value
+        assert_eq!(shop(false).sell("potion", &items, &mut inventory), Ok(15));
+        assert_eq!(
+            shop(true).sell("antidote", &items, &mut inventory),
+            Err(ShopError::NotWanted)
+        );
+        assert_eq!(shop(false).sell("antidote", &items, &mut inventory), Ok(5));
+        assert_eq!(
+            shop(false).sell("junk", &items, &mut inventory),
+            Err(ShopError::NotWanted)
+        );
+        assert_eq!(inventory.money, 20);
+        assert_eq!(inventory.items.get("junk"), Some(&1));
+    }
+
+    #[test]
+    fn selling_what_the_player_does_not_have() {
+        let mut inventory = inventory(0);
+        assert_eq!(
+            shop(false).sell("potion", &item_table(), &mut inventory),
+            Err(ShopError::NoneLeft)
+        );
+        assert_eq!(inventory.money, 0);
+    }
+
+    #[test]
+    fn selling_with_a_full_purse_keeps_the_money_at_the_max() {
+        let mut inventory = inventory(u32::MAX - 10);
+        inventory.add("potion", 1);
+        assert_eq!(
+            shop(false).sell("potion", &item_table(), &mut inventory),
+            Ok(15)
+        );
+        assert_eq!(inventory.money, u32::MAX);
+        assert!(!inventory.items.contains_key("potion"));
+    }
+}
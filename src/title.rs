@@ -0,0 +1,132 @@
+use crate::dex::Garbagedex;
+use crate::flags::Flags;
+use crate::inventory::Inventory;
+use crate::loading::{FontAssets, MapAssets};
+use crate::mods::menu::ModsMenuState;
+use crate::overworld::npc::DefeatedTrainers;
+use crate::overworld::PlayerLocation;
+use crate::party::PlayerParty;
+use crate::quests::Quests;
+use crate::save::SaveGame;
+use crate::settings::menu::SettingsMenuState;
+use crate::ui::{spawn_button, ButtonColors};
+use crate::GameState;
+use bevy::prelude::*;
+
+pub struct TitlePlugin;
+
+/// This plugin draws the title screen with its "Play", "Continue", "Settings" and "Mods" buttons
+/// The title screen is only drawn during the State `GameState::Title` and is removed when that state is exited
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_title.in_schedule(OnEnter(GameState::Title)))
+            .add_system(click_title_button.in_set(OnUpdate(GameState::Title)))
+            .add_system(cleanup_title.in_schedule(OnExit(GameState::Title)));
+    }
+}
+
+#[derive(Component)]
+struct TitleScreen;
+
+#[derive(Component)]
+enum TitleButton {
+    Play,
+    Continue,
+    Settings,
+    Mods,
+}
+
+fn setup_title(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let has_save_game = SaveGame::load().is_some();
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::all(Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(TitleScreen)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "Garbagemon!",
+                    TextStyle {
+                        font: font_assets.fira_sans.clone(),
+                        font_size: 80.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(40.0)),
+                    ..default()
+                }),
+            );
+            let mut buttons = vec![("Play", TitleButton::Play)];
+            if has_save_game {
+                buttons.push(("Continue", TitleButton::Continue));
+            }
+            buttons.push(("Settings", TitleButton::Settings));
+            buttons.push(("Mods", TitleButton::Mods));
+            for (label, button) in buttons {
+                spawn_button(
+                    parent,
+                    label,
+                    font_assets.fira_sans.clone(),
+                    &button_colors,
+                    Size::new(Val::Px(200.0), Val::Px(60.0)),
+                )
+                .insert(button);
+            }
+        });
+}
+
+fn click_title_button(
+    mut commands: Commands,
+    map_assets: Res<MapAssets>,
+    asset_server: Res<AssetServer>,
+    mut state: ResMut<NextState<GameState>>,
+    mut settings_state: ResMut<NextState<SettingsMenuState>>,
+    mut mods_state: ResMut<NextState<ModsMenuState>>,
+    interaction_query: Query<(&Interaction, &TitleButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            TitleButton::Play => {
+                commands.insert_resource(PlayerLocation::new_game(&map_assets));
+                commands.insert_resource(PlayerParty::default());
+                commands.insert_resource(DefeatedTrainers::default());
+                commands.insert_resource(Flags::default());
+                commands.insert_resource(Inventory::default());
+                commands.insert_resource(Quests::default());
+                commands.insert_resource(Garbagedex::default());
+                state.set(GameState::Playing);
+            }
+            TitleButton::Continue => {
+                let Some(save_game) = SaveGame::load() else {
+                    continue;
+                };
+                save_game.restore(&mut commands, &asset_server);
+                state.set(GameState::Playing);
+            }
+            TitleButton::Settings => settings_state.set(SettingsMenuState::Open),
+            TitleButton::Mods => mods_state.set(ModsMenuState::Open),
+        }
+    }
+}
+
+fn cleanup_title(mut commands: Commands, title_screen: Query<Entity, With<TitleScreen>>) {
+    for entity in &title_screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::ui::focus::{Focus, FocusPlugin};
+use crate::ui::theme::ThemePlugin;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+pub mod focus;
+pub mod theme;
+
+pub struct UiPlugin;
+
+/// This plugin holds the pieces shared by every menu in the game,
+/// like the button colors, their hover highlight and keyboard/gamepad focus
+/// The colors come from the theme file, see [`ThemePlugin`]
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonColors>()
+            .add_plugin(FocusPlugin)
+            .add_plugin(ThemePlugin)
+            .add_system(update_button_colors);
+    }
+}
+
+#[derive(Resource, Deserialize, Clone, Debug)]
+pub struct ButtonColors {
+    pub normal: Color,
+    pub hovered: Color,
+    /// Button focused with the keyboard or a gamepad, see [`focus`]
+    pub focused: Color,
+    pub disabled: Color,
+}
+
+impl Default for ButtonColors {
+    fn default() -> Self {
+        ButtonColors {
+            normal: Color::rgb(0.15, 0.15, 0.15),
+            hovered: Color::rgb(0.25, 0.25, 0.25),
+            focused: Color::rgb(0.25, 0.3, 0.45),
+            disabled: Color::rgb(0.35, 0.35, 0.35),
+        }
+    }
+}
+
+impl ButtonColors {
+    /// The background of a button, depending on how the player interacts with it
+    pub fn background(&self, interaction: Interaction, focused: bool, disabled: bool) -> Color {
+        if disabled {
+            return self.disabled;
+        }
+        match interaction {
+            Interaction::Clicked | Interaction::Hovered => self.hovered,
+            Interaction::None if focused => self.focused,
+            Interaction::None => self.normal,
+        }
+    }
+}
+
+/// Buttons with this component are drawn greyed out and ignore the mouse
+#[derive(Component)]
+pub struct Disabled;
+
+/// Spawns a button with a centered label
+/// The returned commands can be used to attach marker components to the button
+pub fn spawn_button<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    label: &str,
+    font: Handle<Font>,
+    button_colors: &ButtonColors,
+    size: Size,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut button = parent.spawn(ButtonBundle {
+        style: Style {
+            size,
+            margin: UiRect::all(Val::Px(5.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: button_colors.normal.into(),
+        ..default()
+    });
+    button.with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font,
+                font_size: 40.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        ));
+    });
+    button
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_button_colors(
+    button_colors: Res<ButtonColors>,
+    focus: Res<Focus>,
+    mut interaction_query: Query<
+        (
+            Entity,
+            &Interaction,
+            &mut BackgroundColor,
+            Option<&Disabled>,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (entity, interaction, mut color, disabled) in &mut interaction_query {
+        let focused = focus.entity == Some(entity);
+        *color = button_colors
+            .background(*interaction, focused, disabled.is_some())
+            .into();
+    }
+}
@@ -0,0 +1,249 @@
+use crate::actions::{set_menu_actions, Actions, NavigationDirection};
+use crate::ui::{update_button_colors, ButtonColors, Disabled};
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+
+/// Moving sideways away from the pressed direction counts this much more than moving along it
+/// when picking the next button, so the focus prefers buttons in the same row or column
+const ACROSS_DIRECTION_PENALTY: f32 = 2.0;
+
+pub struct FocusPlugin;
+
+/// This plugin lets the player move a focus between buttons with the keyboard or a gamepad
+/// and press the focused button with the confirm control, so menus don't need a mouse
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Focus>()
+            .add_systems(
+                (release_pressed_button, navigate_focus)
+                    .chain()
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(UiSystem::Focus)
+                    .after(set_menu_actions),
+            )
+            .add_system(highlight_focus.after(update_button_colors));
+    }
+}
+
+/// The button currently focused for keyboard and gamepad navigation
+#[derive(Resource, Default)]
+pub struct Focus {
+    pub entity: Option<Entity>,
+    /// Menus that read raw key presses (like key rebinding) set this to keep the focus still
+    pub suspended: bool,
+    /// Button "clicked" through the focus last frame, to be released again
+    pressed: Option<Entity>,
+}
+
+/// Pressing the cancel control clicks this button, if it belongs to the top most menu
+#[derive(Component)]
+pub struct CancelButton;
+
+struct Candidate {
+    entity: Entity,
+    position: Vec2,
+    cancel: bool,
+}
+
+#[allow(clippy::type_complexity)]
+fn navigate_focus(
+    actions: Res<Actions>,
+    mut focus: ResMut<Focus>,
+    buttons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &ComputedVisibility,
+            Option<&CancelButton>,
+        ),
+        (With<Button>, Without<Disabled>),
+    >,
+    parents: Query<&Parent>,
+    z_indices: Query<&ZIndex>,
+    mut interactions: Query<&mut Interaction>,
+) {
+    let candidates = focusable_buttons(&buttons, &parents, &z_indices);
+    if let Some(entity) = focus.entity {
+        if !candidates
+            .iter()
+            .any(|candidate| candidate.entity == entity)
+        {
+            focus.entity = None;
+        }
+    }
+    if focus.suspended {
+        return;
+    }
+    if actions.cancel {
+        if let Some(cancel) = candidates.iter().find(|candidate| candidate.cancel) {
+            press(&mut focus, &mut interactions, cancel.entity);
+        }
+        return;
+    }
+    if actions.navigation.is_none() && !actions.confirm {
+        return;
+    }
+    let Some(first) = candidates.first() else {
+        return;
+    };
+    let Some(current) = focus.entity else {
+        // the first key press only reveals the focus
+        focus.entity = Some(first.entity);
+        return;
+    };
+    if actions.confirm {
+        press(&mut focus, &mut interactions, current);
+        return;
+    }
+    let Some(direction) = actions.navigation else {
+        return;
+    };
+    let index = candidates
+        .iter()
+        .position(|candidate| candidate.entity == current)
+        .unwrap_or(0);
+    let next = match direction {
+        NavigationDirection::Previous => (index + candidates.len() - 1) % candidates.len(),
+        NavigationDirection::Next => (index + 1) % candidates.len(),
+        NavigationDirection::Up => closest_in_direction(&candidates, index, Vec2::NEG_Y),
+        NavigationDirection::Down => closest_in_direction(&candidates, index, Vec2::Y),
+        NavigationDirection::Left => closest_in_direction(&candidates, index, Vec2::NEG_X),
+        NavigationDirection::Right => closest_in_direction(&candidates, index, Vec2::X),
+    };
+    focus.entity = Some(candidates[next].entity);
+}
+
+/// "Clicks" the button, just like the mouse would
+fn press(focus: &mut Focus, interactions: &mut Query<&mut Interaction>, entity: Entity) {
+    if let Ok(mut interaction) = interactions.get_mut(entity) {
+        *interaction = Interaction::Clicked;
+        focus.pressed = Some(entity);
+    }
+}
+
+/// Index of the button closest to `candidates[from]` in `direction` (in UI coordinates)
+///
+/// If there is none, the focus wraps around to the button furthest in the opposite direction.
+fn closest_in_direction(candidates: &[Candidate], from: usize, direction: Vec2) -> usize {
+    let origin = candidates[from].position;
+    let offsets = candidates
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != from)
+        .map(|(index, candidate)| {
+            let offset = candidate.position - origin;
+            let along = offset.dot(direction);
+            let across = offset.perp_dot(direction).abs();
+            (index, along, across)
+        });
+    let closest = offsets
+        .clone()
+        // a little slack, so buttons in the same row don't count as above or below each other
+        .filter(|(_, along, _)| *along > 1.0)
+        .map(|(index, along, across)| (index, along + across * ACROSS_DIRECTION_PENALTY))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    let wrapped = || {
+        offsets
+            .filter(|(_, along, _)| *along < -1.0)
+            .map(|(index, along, across)| (index, along + across * ACROSS_DIRECTION_PENALTY))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    };
+    closest
+        .or_else(wrapped)
+        .map(|(index, _)| index)
+        .unwrap_or(from)
+}
+
+/// All buttons of the top most menu, in tab order
+#[allow(clippy::type_complexity)]
+fn focusable_buttons(
+    buttons: &Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &ComputedVisibility,
+            Option<&CancelButton>,
+        ),
+        (With<Button>, Without<Disabled>),
+    >,
+    parents: &Query<&Parent>,
+    z_indices: &Query<&ZIndex>,
+) -> Vec<Candidate> {
+    let layer_of = |entity: Entity| {
+        let root = parents.iter_ancestors(entity).last().unwrap_or(entity);
+        match z_indices.get(root) {
+            Ok(ZIndex::Global(z)) => *z,
+            _ => 0,
+        }
+    };
+    let visible: Vec<(Candidate, i32)> = buttons
+        .iter()
+        .filter(|(_, _, visibility, _)| visibility.is_visible())
+        .map(|(entity, transform, _, cancel)| {
+            let candidate = Candidate {
+                entity,
+                position: transform.translation().truncate(),
+                cancel: cancel.is_some(),
+            };
+            (candidate, layer_of(entity))
+        })
+        .collect();
+    let Some(top_layer) = visible.iter().map(|(_, layer)| *layer).max() else {
+        return vec![];
+    };
+    let mut candidates: Vec<Candidate> = visible
+        .into_iter()
+        .filter(|(_, layer)| *layer == top_layer)
+        .map(|(candidate, _)| candidate)
+        .collect();
+    // UI positions grow downwards, so this sorts top to bottom, then left to right
+    candidates.sort_by(|a, b| {
+        a.position
+            .y
+            .total_cmp(&b.position.y)
+            .then(a.position.x.total_cmp(&b.position.x))
+    });
+    candidates
+}
+
+fn release_pressed_button(
+    mut focus: ResMut<Focus>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut interactions: Query<&mut Interaction>,
+) {
+    let Some(entity) = focus.pressed.take() else {
+        return;
+    };
+    if mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+    if let Ok(mut interaction) = interactions.get_mut(entity) {
+        interaction.set_if_neq(Interaction::None);
+    }
+}
+
+fn highlight_focus(
+    focus: Res<Focus>,
+    mut highlighted: Local<Option<Entity>>,
+    button_colors: Res<ButtonColors>,
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), With<Button>>,
+) {
+    if *highlighted == focus.entity {
+        return;
+    }
+    if let Some(previous) = highlighted.take() {
+        if let Ok((interaction, mut color)) = buttons.get_mut(previous) {
+            if *interaction == Interaction::None {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+    if let Some(entity) = focus.entity {
+        if let Ok((interaction, mut color)) = buttons.get_mut(entity) {
+            if *interaction == Interaction::None {
+                *color = button_colors.focused.into();
+            }
+        }
+    }
+    *highlighted = focus.entity;
+}
@@ -0,0 +1,71 @@
+use crate::data::{RonAsset, RonAssetApp};
+use crate::loading::UiAssets;
+use crate::ui::focus::Focus;
+use crate::ui::{ButtonColors, Disabled};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+pub struct ThemePlugin;
+
+/// This plugin takes the [`ButtonColors`] from the theme file once it is loaded
+/// and again whenever the file changes on disk
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_ron_asset::<Theme>().add_system(apply_theme);
+    }
+}
+
+/// The colors of the menus, see `assets/ui/base.theme.ron`
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "5a9e2c71-3b8f-4d06-a1e4-8f72c0d9b35e"]
+pub struct Theme {
+    pub buttons: ButtonColors,
+}
+
+impl RonAsset for Theme {
+    const EXTENSIONS: &'static [&'static str] = &["theme.ron"];
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_theme(
+    mut events: EventReader<AssetEvent<Theme>>,
+    ui_assets: Option<Res<UiAssets>>,
+    themes: Res<Assets<Theme>>,
+    focus: Res<Focus>,
+    mut button_colors: ResMut<ButtonColors>,
+    mut buttons: Query<
+        (
+            Entity,
+            &Interaction,
+            &mut BackgroundColor,
+            Option<&Disabled>,
+        ),
+        With<Button>,
+    >,
+) {
+    let Some(ui_assets) = ui_assets else {
+        events.clear();
+        return;
+    };
+    let modified = events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+            *handle == ui_assets.theme
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    if !modified && !ui_assets.is_added() {
+        return;
+    }
+    let Some(theme) = themes.get(&ui_assets.theme) else {
+        return;
+    };
+    *button_colors = theme.buttons.clone();
+    // the buttons on screen are recolored right away, without waiting for the mouse
+    for (entity, interaction, mut color, disabled) in &mut buttons {
+        let focused = focus.entity == Some(entity);
+        *color = button_colors
+            .background(*interaction, focused, disabled.is_some())
+            .into();
+    }
+}
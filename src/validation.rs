@@ -0,0 +1,519 @@
+//! Checks every data file in `assets` and the references between them,
+//! so broken content shows up in `cargo test` instead of in the middle of a game
+//!
+//! Content packs are checked merged on top of the base files, the way the game loads them.
+//!
+//! Run it on its own with `cargo test validation`.
+
+use crate::audio::music::MusicTable;
+use crate::audio::sfx::SoundTable;
+use crate::creature::animations::CreatureAnimations;
+use crate::creature::moves::{Cue, MoveTable};
+use crate::creature::species::SpeciesTable;
+use crate::data::RonAsset;
+use crate::dialogue::script::{DialogueAction, DialogueScript, Next};
+use crate::flags::Condition;
+use crate::inventory::items::ItemTable;
+use crate::overworld::map::Map;
+use crate::party::PartyMember;
+use crate::quests::QuestTable;
+use crate::shop::stock::ShopTable;
+use crate::ui::theme::Theme;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Something wrong in one field of a data file
+struct Problem {
+    /// Path relative to the assets folder
+    file: String,
+    field: String,
+    message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.file, self.field, self.message)
+    }
+}
+
+/// Every data file, keyed by its path relative to the assets folder
+#[derive(Default)]
+struct Content {
+    species: BTreeMap<String, SpeciesTable>,
+    animations: BTreeMap<String, CreatureAnimations>,
+    moves: BTreeMap<String, MoveTable>,
+    items: BTreeMap<String, ItemTable>,
+    shops: BTreeMap<String, ShopTable>,
+    quests: BTreeMap<String, QuestTable>,
+    maps: BTreeMap<String, Map>,
+    dialogue: BTreeMap<String, DialogueScript>,
+    music: BTreeMap<String, MusicTable>,
+    sounds: BTreeMap<String, SoundTable>,
+}
+
+/// Ids that can be referenced from any file
+#[derive(Default)]
+struct Ids {
+    species: BTreeSet<String>,
+    moves: BTreeSet<String>,
+    items: BTreeSet<String>,
+    shops: BTreeSet<String>,
+    quests: BTreeSet<String>,
+    nodes: BTreeSet<String>,
+}
+
+struct Validator {
+    assets: PathBuf,
+    /// Folders of the content packs layered on top of `assets`, in load order
+    packs: Vec<PathBuf>,
+    problems: Vec<Problem>,
+}
+
+impl Validator {
+    fn report(&mut self, file: &str, field: impl Into<String>, message: impl Into<String>) {
+        self.problems.push(Problem {
+            file: file.to_string(),
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// The base folder followed by the packs
+    fn layers(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.assets).chain(&self.packs)
+    }
+
+    /// Every file in any layer ending with the extension, like `species.ron`
+    fn files(&self, extension: &str) -> BTreeSet<String> {
+        let mut files = BTreeSet::new();
+        for layer in self.layers() {
+            let mut folders = vec![layer.clone()];
+            while let Some(folder) = folders.pop() {
+                let Ok(entries) = fs::read_dir(&folder) else {
+                    continue;
+                };
+                for path in entries.flatten().map(|entry| entry.path()) {
+                    if path.is_dir() {
+                        folders.push(path);
+                    } else if path.to_string_lossy().ends_with(&format!(".{extension}")) {
+                        let relative = path.strip_prefix(layer).unwrap_or(&path);
+                        files.insert(relative.to_string_lossy().replace('\\', "/"));
+                    }
+                }
+            }
+        }
+        files
+    }
+
+    /// Loads every file with the extension, merging the layers of a file in load order
+    fn load_all<T>(
+        &mut self,
+        extension: &str,
+        parse: impl Fn(&[u8]) -> Result<T, String>,
+        merge: impl Fn(&mut T, T),
+    ) -> BTreeMap<String, T> {
+        let mut loaded = BTreeMap::new();
+        for file in self.files(extension) {
+            let layers: Vec<(String, PathBuf)> = self
+                .layers()
+                .map(|layer| (layer_name(&self.assets, layer, &file), layer.join(&file)))
+                .filter(|(_, path)| path.is_file())
+                .collect();
+            let mut merged: Option<T> = None;
+            for (name, path) in layers {
+                let parsed = fs::read(path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|bytes| parse(&bytes));
+                match (parsed, &mut merged) {
+                    (Ok(patch), Some(merged)) => merge(merged, patch),
+                    (Ok(content), None) => merged = Some(content),
+                    (Err(error), _) => self.report(&name, "file", error),
+                }
+            }
+            if let Some(content) = merged {
+                loaded.insert(file, content);
+            }
+        }
+        loaded
+    }
+
+    fn load_ron<T: RonAsset>(&mut self, extension: &str) -> BTreeMap<String, T> {
+        self.load_all(
+            extension,
+            |bytes| ron::de::from_bytes(bytes).map_err(|error| error.to_string()),
+            T::merge,
+        )
+    }
+
+    fn load(&mut self) -> Content {
+        // themes don't reference anything, so parsing them is all there is to check
+        self.load_ron::<Theme>("theme.ron");
+        Content {
+            species: self.load_ron("species.ron"),
+            animations: self.load_ron("anim.ron"),
+            moves: self.load_ron("moves.ron"),
+            items: self.load_ron("items.ron"),
+            shops: self.load_ron("shops.ron"),
+            quests: self.load_ron("quests.ron"),
+            // packs replace whole maps
+            maps: self.load_all(
+                "map.ron",
+                |bytes| Map::from_ron(bytes).map_err(|error| error.to_string()),
+                |map, patch| *map = patch,
+            ),
+            dialogue: self.load_ron("dialogue.ron"),
+            music: self.load_ron("music.ron"),
+            sounds: self.load_ron("sfx.ron"),
+        }
+    }
+
+    fn check_path(&mut self, file: &str, field: impl Into<String>, path: &str) {
+        if !self.layers().any(|layer| layer.join(path).is_file()) {
+            self.report(file, field, format!("\"{path}\" does not exist"));
+        }
+    }
+
+    fn check_id(
+        &mut self,
+        file: &str,
+        field: impl Into<String>,
+        kind: &str,
+        known: &BTreeSet<String>,
+        id: &str,
+    ) {
+        if !known.contains(id) {
+            self.report(file, field, format!("unknown {kind} \"{id}\""));
+        }
+    }
+
+    fn check_member(&mut self, file: &str, field: &str, member: &PartyMember, ids: &Ids) {
+        let species = &member.species;
+        self.check_id(
+            file,
+            format!("{field}.species"),
+            "species",
+            &ids.species,
+            species,
+        );
+        for (index, move_id) in member.moves.iter().flatten().enumerate() {
+            let field = format!("{field}.moves[{index}]");
+            self.check_id(file, field, "move", &ids.moves, move_id);
+        }
+        if let Some(item) = &member.held_item {
+            let field = format!("{field}.held_item");
+            self.check_id(file, field, "item", &ids.items, item);
+        }
+        if member.level == 0 {
+            self.report(file, format!("{field}.level"), "levels start at 1");
+        }
+    }
+
+    fn check_condition(&mut self, file: &str, field: &str, condition: &Condition, ids: &Ids) {
+        match condition {
+            Condition::QuestActive(quest) | Condition::QuestCompleted(quest) => {
+                self.check_id(file, field, "quest", &ids.quests, quest);
+            }
+            Condition::Not(condition) => self.check_condition(file, field, condition, ids),
+            Condition::All(conditions) | Condition::Any(conditions) => {
+                for condition in conditions {
+                    self.check_condition(file, field, condition, ids);
+                }
+            }
+            Condition::Flag(_)
+            | Condition::NotFlag(_)
+            | Condition::AtLeast(..)
+            | Condition::Below(..) => {}
+        }
+    }
+
+    fn validate(&mut self, content: &Content) {
+        let ids = Ids {
+            species: keys(content.species.values().map(|table| &table.species)),
+            moves: keys(content.moves.values().map(|table| &table.moves)),
+            items: keys(content.items.values().map(|table| &table.items)),
+            shops: keys(content.shops.values().map(|table| &table.shops)),
+            quests: keys(content.quests.values().map(|table| &table.quests)),
+            nodes: keys(content.dialogue.values().map(|script| &script.nodes)),
+        };
+
+        for (file, table) in &content.species {
+            for (id, species) in &table.species {
+                self.check_path(file, format!("{id}.sprite"), &species.sprite);
+                if let Some(animations) = &species.animations {
+                    if !content.animations.contains_key(animations) {
+                        let message = format!("unknown animations \"{animations}\"");
+                        self.report(file, format!("{id}.animations"), message);
+                    }
+                }
+                if species.moves.is_empty() {
+                    self.report(
+                        file,
+                        format!("{id}.moves"),
+                        "a species needs at least one move",
+                    );
+                }
+                for (index, move_id) in species.moves.iter().enumerate() {
+                    let field = format!("{id}.moves[{index}]");
+                    self.check_id(file, field, "move", &ids.moves, move_id);
+                }
+            }
+        }
+
+        for (file, table) in &content.moves {
+            for (id, used_move) in &table.moves {
+                for (index, cue) in used_move.sequence.iter().enumerate() {
+                    let field = format!("{id}.sequence[{index}]");
+                    if cue.seconds() < 0. {
+                        self.report(file, &field, "can't take less than 0 seconds");
+                    }
+                    if let Cue::Projectile {
+                        sprite: Some(sprite),
+                        ..
+                    } = cue
+                    {
+                        self.check_path(file, format!("{field}.sprite"), sprite);
+                    }
+                }
+            }
+        }
+
+        for (file, animations) in &content.animations {
+            self.check_path(file, "sheet", &animations.sheet);
+            let frame_count = animations.columns * animations.rows;
+            for (state, clip) in &animations.clips {
+                if clip.fps <= 0. {
+                    self.report(file, format!("{state:?}.fps"), "needs to be above 0");
+                }
+                for (index, frame) in clip.frames.iter().enumerate() {
+                    if *frame >= frame_count {
+                        let message = format!("the sheet only has {frame_count} frames");
+                        self.report(file, format!("{state:?}.frames[{index}]"), message);
+                    }
+                }
+            }
+        }
+
+        for (file, table) in &content.items {
+            for (id, item) in &table.items {
+                if let Some(icon) = &item.icon {
+                    self.check_path(file, format!("{id}.icon"), icon);
+                }
+                if item.effect.is_none() && item.held.is_empty() {
+                    self.report(file, id.as_str(), "the item can neither be used nor held");
+                }
+            }
+        }
+
+        for (file, table) in &content.shops {
+            for (id, shop) in &table.shops {
+                for (index, stock) in shop.stock.iter().enumerate() {
+                    let field = format!("{id}.stock[{index}].item");
+                    self.check_id(file, field, "item", &ids.items, &stock.item);
+                }
+            }
+        }
+
+        for (file, table) in &content.quests {
+            for (id, quest) in &table.quests {
+                for (index, objective) in quest.objectives.iter().enumerate() {
+                    let field = format!("{id}.objectives[{index}].done");
+                    self.check_condition(file, &field, &objective.done, &ids);
+                }
+            }
+        }
+
+        for (file, map) in &content.maps {
+            for (index, warp) in map.warps.iter().enumerate() {
+                if !content.maps.contains_key(&warp.map) {
+                    let message = format!("unknown map \"{}\"", warp.map);
+                    self.report(file, format!("warps[{index}].map"), message);
+                }
+                if let Some(condition) = &warp.condition {
+                    let field = format!("warps[{index}].condition");
+                    self.check_condition(file, &field, condition, &ids);
+                }
+                if let Some(node) = &warp.locked {
+                    let field = format!("warps[{index}].locked");
+                    self.check_id(file, field, "dialogue node", &ids.nodes, node);
+                }
+            }
+            for (index, prop) in map.props.iter().enumerate() {
+                if let Some(node) = &prop.script {
+                    let field = format!("props[{index}].script");
+                    self.check_id(file, field, "dialogue node", &ids.nodes, node);
+                }
+            }
+            for (index, zone) in map.encounters.iter().enumerate() {
+                for (species_index, (species, _)) in zone.species.iter().enumerate() {
+                    let field = format!("encounters[{index}].species[{species_index}]");
+                    self.check_id(file, field, "species", &ids.species, species);
+                }
+                if zone.levels.0 > zone.levels.1 {
+                    let field = format!("encounters[{index}].levels");
+                    self.report(file, field, "the lowest level is above the highest");
+                }
+                if let Some(condition) = &zone.condition {
+                    let field = format!("encounters[{index}].condition");
+                    self.check_condition(file, &field, condition, &ids);
+                }
+            }
+            for npc in &map.npcs {
+                if let Some(node) = &npc.script {
+                    let field = format!("npcs.{}.script", npc.id);
+                    self.check_id(file, field, "dialogue node", &ids.nodes, node);
+                }
+                for (index, member) in npc.party.iter().enumerate() {
+                    let field = format!("npcs.{}.party[{index}]", npc.id);
+                    self.check_member(file, &field, member, &ids);
+                }
+            }
+        }
+
+        for (file, script) in &content.dialogue {
+            for (id, node) in &script.nodes {
+                if let Some(portrait) = &node.portrait {
+                    self.check_path(file, format!("{id}.portrait"), portrait);
+                }
+                match &node.next {
+                    Next::End => {}
+                    Next::Goto(target) => {
+                        let field = format!("{id}.next");
+                        self.check_id(file, field, "dialogue node", &ids.nodes, target);
+                    }
+                    Next::Choices(choices) => {
+                        for (index, choice) in choices.iter().enumerate() {
+                            let field = format!("{id}.next.choices[{index}]");
+                            if let Some(target) = &choice.goto {
+                                self.check_id(file, &field, "dialogue node", &ids.nodes, target);
+                            }
+                            if let Some(condition) = &choice.condition {
+                                self.check_condition(file, &field, condition, &ids);
+                            }
+                        }
+                    }
+                    Next::Branch {
+                        condition,
+                        then,
+                        otherwise,
+                    } => {
+                        let field = format!("{id}.next");
+                        self.check_condition(file, &field, condition, &ids);
+                        for target in [then, otherwise] {
+                            self.check_id(file, &field, "dialogue node", &ids.nodes, target);
+                        }
+                    }
+                }
+                for (index, action) in node.actions.iter().enumerate() {
+                    let field = format!("{id}.actions[{index}]");
+                    match action {
+                        DialogueAction::StartQuest(quest) => {
+                            self.check_id(file, field, "quest", &ids.quests, quest);
+                        }
+                        DialogueAction::GiveItem(item, _) => {
+                            self.check_id(file, field, "item", &ids.items, item);
+                        }
+                        DialogueAction::StartBattle(party) => {
+                            for (member_index, member) in party.iter().enumerate() {
+                                let field = format!("{field}[{member_index}]");
+                                self.check_member(file, &field, member, &ids);
+                            }
+                        }
+                        DialogueAction::OpenShop(shop) => {
+                            self.check_id(file, field, "shop", &ids.shops, shop);
+                        }
+                        DialogueAction::SetFlag(_)
+                        | DialogueAction::ClearFlag(_)
+                        | DialogueAction::ChangeFlag(_)
+                        | DialogueAction::HealParty => {}
+                    }
+                }
+            }
+        }
+
+        for (file, table) in &content.music {
+            for (cue, track) in &table.tracks {
+                self.check_path(file, format!("{cue:?}.path"), &track.path);
+            }
+        }
+        for (file, table) in &content.sounds {
+            for (sfx, sound) in &table.sounds {
+                self.check_path(file, format!("{sfx:?}.path"), &sound.path);
+            }
+        }
+    }
+}
+
+/// How problems refer to a file of a layer, files of packs start with the pack's folder
+fn layer_name(assets: &Path, layer: &Path, file: &str) -> String {
+    match layer.file_name() {
+        Some(folder) if layer != assets => format!("{}/{file}", folder.to_string_lossy()),
+        _ => file.to_string(),
+    }
+}
+
+fn keys<'a, T: 'a>(tables: impl Iterator<Item = &'a BTreeMap<String, T>>) -> BTreeSet<String> {
+    tables.flat_map(|table| table.keys().cloned()).collect()
+}
+
+/// Loads and checks everything in the assets folder with the packs on top,
+/// returns every problem found
+fn validate(assets: &Path, packs: &[PathBuf]) -> Vec<String> {
+    let mut validator = Validator {
+        assets: assets.to_path_buf(),
+        packs: packs.to_vec(),
+        problems: vec![],
+    };
+    let content = validator.load();
+    validator.validate(&content);
+    validator
+        .problems
+        .iter()
+        .map(|problem| problem.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assets_are_valid() {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let problems = validate(&assets, &[]);
+        assert!(
+            problems.is_empty(),
+            "{} problems in the assets:\n{}",
+            problems.len(),
+            problems.join("\n")
+        );
+    }
+
+    #[test]
+    fn assets_with_the_example_pack_are_valid() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let packs = [root.join("mods/example_pack")];
+        let problems = validate(&root.join("assets"), &packs);
+        assert!(
+            problems.is_empty(),
+            "{} problems with the example pack:\n{}",
+            problems.len(),
+            problems.join("\n")
+        );
+    }
+
+    #[test]
+    fn broken_content_is_reported() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/broken_assets");
+        let problems = validate(&fixture, &[]);
+        let expected = [
+            "data/broken.species.ron: sludgemon.sprite: \"textures/creatures/sludgemon.png\" does not exist",
+            "data/broken.species.ron: sludgemon.moves[1]: unknown move \"ooze\"",
+            "data/broken.moves.ron: scratch.sequence[0]: can't take less than 0 seconds",
+            "data/broken.shops.ron: stall.stock[1].item: unknown item \"golden_snack\"",
+            "dialogue/broken.dialogue.ron: bully.actions[0][0].species: unknown species \"nobodymon\"",
+        ];
+        assert_eq!(problems, expected);
+    }
+}